@@ -1192,14 +1192,7 @@ export async function runTradeExecutor() {
                 // Get portfolio state from CRDT Guardian
                 try {
                     const portfolioState = titanGuardian.getPortfolioState();
-                    const state = JSON.parse(portfolioState || '{}');
-
-                    // Calculate implied PnL from state (if positions exist)
-                    const positions = Object.values(state || {}) as any[];
-                    const totalPnl = positions.reduce((sum: number, pos: any) => {
-                        if (pos && typeof pos.pnl === 'number') return sum + pos.pnl;
-                        return sum;
-                    }, 0);
+                    const totalPnl = portfolioState.totals.unrealizedPnl;
 
                     // Dynamic sizing based on PnL
                     if (totalPnl > 100) {
@@ -1393,19 +1386,27 @@ export async function runTradeExecutor() {
 
                 // 1. Native OFI Calculation (AVX-512 Optimized)
                 // FIX: Convert string arrays from WEEX API to number tuples for Rust
+                // Rust rejects non-positive prices and bad sizes: drop those levels here
+                const isValidLevel = ([price, qty]: number[]) =>
+                    Number.isFinite(price) && price > 0 && Number.isFinite(qty) && qty >= 0;
                 const bidsForRust = (orderBookSnapshot.bids || []).slice(0, 10).map((b: any) => [
                     parseFloat(b[0] || b.price || '0'),
                     parseFloat(b[1] || b.qty || b.quantity || '0')
-                ]);
+                ]).filter(isValidLevel);
                 const asksForRust = (orderBookSnapshot.asks || []).slice(0, 10).map((a: any) => [
                     parseFloat(a[0] || a.price || '0'),
                     parseFloat(a[1] || a.qty || a.quantity || '0')
-                ]);
+                ]).filter(isValidLevel);
 
-                const nativeOfi = titanGuardian.calculateOfi(
-                    JSON.stringify(bidsForRust),
-                    JSON.stringify(asksForRust)
-                );
+                let nativeOfi = 0;
+                try {
+                    nativeOfi = titanGuardian.calculateOfi(
+                        JSON.stringify(bidsForRust),
+                        JSON.stringify(asksForRust)
+                    );
+                } catch (e: any) {
+                    logger.warn(`  ⚠️ Native OFI failed, assuming neutral book: ${e.message}`);
+                }
 
                 // 2. Datalog Logic Validation (with ADX, RSI, Position Limit)
                 let currentPositionCount = 0;
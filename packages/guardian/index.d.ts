@@ -3,21 +3,621 @@
 
 /* auto-generated by NAPI-RS */
 
+/**
+ * Microstructure features derived from a single order book snapshot.
+ * Bids are expected best-first (descending price), asks best-first (ascending).
+ */
+export interface TradePrint {
+  price: number
+  size: number
+  /** Aggressor side ("BUY"/"SELL"). Inferred with the tick rule when absent. */
+  side?: string
+}
+export interface FlowMetrics {
+  /** Volume-synchronised probability of informed trading (0.0 to 1.0) */
+  vpin: number
+  /** Buy minus sell aggressor volume over the VPIN window */
+  signedFlow: number
+  /** Signed flow normalised by window volume (-1.0 to 1.0) */
+  flowImbalance: number
+  /** Buy minus sell aggressor volume since the first print */
+  cumulativeSignedFlow: number
+  buyVolume: number
+  sellVolume: number
+  completedBuckets: number
+  tradeCount: number
+  lastPrice: number
+}
+/** An order submitted by the bot, as tracked by the guardian. */
+export interface Order {
+  id: string
+  symbol: string
+  /** "BUY" or "SELL" */
+  side: string
+  quantity: number
+  /** Limit price, None for market orders */
+  price?: number
+  filledQuantity: number
+  averageFillPrice: number
+  /** "NEW", "ACKNOWLEDGED", "PARTIALLY_FILLED", "FILLED", "CANCELLED" or "REJECTED" */
+  status: string
+  /** Rejection or cancellation reason */
+  reason?: string
+  strategyId?: string
+  signalHash?: string
+  /** "MathGuardian", "NeuralCortex" or "SymbolicConsensus" */
+  signalSource?: string
+  createdMs: number
+  updatedMs: number
+}
+/** New order to track. `id` should be the client order id sent to the exchange. */
+export interface OrderInput {
+  id: string
+  symbol: string
+  side: string
+  quantity: number
+  /** Limit price; omit for market orders */
+  price?: number
+  strategyId?: string
+  signalHash?: string
+  /** Tags the order's fills for per-source attribution */
+  signalSource?: string
+}
+/** PnL of one (strategy, signal source, symbol) book. */
+export interface AttributionBook {
+  strategyId: string
+  signalSource: string
+  symbol: string
+  /** Net quantity this book's fills leave open */
+  quantity: number
+  realizedPnl: number
+  /** Open quantity valued at the symbol's current mark */
+  unrealizedPnl: number
+  fees: number
+  /** Realised + unrealised - fees */
+  netPnl: number
+  fillCount: number
+}
+export interface AttributionLine {
+  /** Strategy ID, signal source or symbol, depending on the breakdown */
+  key: string
+  realizedPnl: number
+  unrealizedPnl: number
+  fees: number
+  netPnl: number
+  fillCount: number
+}
+export interface AttributionReport {
+  books: Array<AttributionBook>
+  byStrategy: Array<AttributionLine>
+  bySource: Array<AttributionLine>
+  bySymbol: Array<AttributionLine>
+}
+/** Account equity recorded after a portfolio commit. */
+export interface EquityPoint {
+  timestampMs: number
+  equity: number
+}
+export interface AnalyticsOptions {
+  /** Only equity points and trades closed at or after this time (unix ms) */
+  sinceMs?: number
+  untilMs?: number
+  /** Resampling bucket for returns (default 1h) */
+  sampleIntervalMs?: number
+  /** Samples per rolling return (default 24) */
+  rollingWindow?: number
+}
+export interface PerformanceReport {
+  /**
+   * Resampled equity points the return statistics are based on (one per
+   * interval, gaps carry the last equity forward)
+   */
+  samples: number
+  startEquity: number
+  endEquity: number
+  highWaterMark: number
+  /** Current drawdown from the high-water mark (fraction) */
+  drawdown: number
+  maxDrawdown: number
+  totalReturn: number
+  /** Return over each `rolling_window` samples, oldest first */
+  rollingReturns: Array<number>
+  /** Annualised from per-sample returns */
+  sharpe: number
+  sortino: number
+  /** Round trips (open to flat, or open to flip) closed in the window */
+  tradeCount: number
+  winRate: number
+  /** Gross profit / gross loss of closed trades (Infinity without losses) */
+  profitFactor: number
+  avgTradeDurationMs: number
+}
+/** Exchange trading rules for one symbol. */
+export interface InstrumentSpec {
+  symbol: string
+  /** Price increment */
+  tickSize: number
+  /** Quantity increment */
+  lotSize: number
+  minQuantity?: number
+}
+export interface FlattenOptions {
+  /**
+   * "MARKET" (default) or "LIMIT" (aggressive limit through the mark;
+   * positions without a mark are skipped)
+   */
+  orderType?: string
+  /** How far through the mark aggressive limits are priced (default 50bps) */
+  slippageBps?: number
+  /** Only flatten these symbols (all positions when omitted) */
+  symbols?: Array<string>
+}
+/** One reduce-only close order, ready to submit as-is. */
+export interface CloseOrder {
+  /** Submission order: most at-risk positions first */
+  sequence: number
+  clientOrderId: string
+  symbol: string
+  /** "BUY" closes a short, "SELL" closes a long */
+  side: string
+  quantity: number
+  /** Position left open by rounding `quantity` down to the lot size */
+  dust: number
+  /** "MARKET" or "LIMIT" */
+  orderType: string
+  /** Limit price, rounded to the tick away from the mark */
+  price?: number
+  reduceOnly: boolean
+  notional: number
+  unrealizedPnl: number
+  /** |mark - liquidation| / mark of the position being closed */
+  liquidationDistance?: number
+}
+/** A position the plan could not close, and why. */
+export interface SkippedClose {
+  symbol: string
+  quantity: number
+  reason: string
+}
+export interface FlattenPlan {
+  /** Tracked open orders to cancel before submitting the closes */
+  cancelOrderIds: Array<string>
+  orders: Array<CloseOrder>
+  skipped: Array<SkippedClose>
+  createdMs: number
+}
+/** Account-level inputs, stored in the portfolio document. */
+export interface AccountSettings {
+  /** Collateral as reported by the exchange (realised PnL, fees and funding included) */
+  walletBalance: number
+  maintenanceMarginRate: number
+  /** Risk limit: maximum used margin / equity (0.0 to 1.0) */
+  maxMarginUsage: number
+  /** Risk limit: minimum |mark - liquidation| / mark */
+  minLiquidationDistance: number
+  /**
+   * Realised PnL - fees + funding already booked when `wallet_balance` was
+   * set; only what is booked after that moves equity
+   */
+  bookedPnlAtBalance: number
+}
+/** Partial update of `AccountSettings`; omitted fields are left unchanged. */
+export interface AccountConfig {
+  walletBalance?: number
+  maintenanceMarginRate?: number
+  maxMarginUsage?: number
+  minLiquidationDistance?: number
+}
+export interface PositionMargin {
+  symbol: string
+  quantity: number
+  leverage: number
+  /** |notional| / leverage at the current mark */
+  usedMargin: number
+  /** Estimated (isolated margin) liquidation price, None when flat or unreachable */
+  liquidationPrice?: number
+  /** |mark - liquidation| / mark */
+  liquidationDistance?: number
+}
+export interface AccountState {
+  walletBalance: number
+  /** Realised PnL - fees + funding booked since `wallet_balance` was set */
+  settledPnl: number
+  unrealizedPnl: number
+  /** Wallet balance + settled PnL + unrealised PnL */
+  equity: number
+  usedMargin: number
+  freeMargin: number
+  /** Used margin / equity (Infinity when equity is exhausted) */
+  marginUsage: number
+  positions: Array<PositionMargin>
+}
+export interface BookFeatures {
+  midPrice: number
+  spread: number
+  /** Size-weighted L1 price: (ask * bid_qty + bid * ask_qty) / (bid_qty + ask_qty) */
+  microprice: number
+  /** Level-1 queue imbalance (-1.0 to 1.0) */
+  queueImbalance: number
+  /** Multi-level analogue of the microprice using side VWAPs and depths */
+  weightedMid: number
+  /** Depth imbalance with exp(-decay * level) weights (-1.0 to 1.0) */
+  depthPressure: number
+  /** Cumulative bid volume per unit of distance from mid */
+  bidSlope: number
+  /** Cumulative ask volume per unit of distance from mid */
+  askSlope: number
+  /** Average of both side slopes (overall book steepness) */
+  bookSlope: number
+}
+
+export interface WallInfo {
+  price: number
+  size: number
+  /** Time the wall has been continuously resting (ms) */
+  persistenceMs: number
+  /** Closest distance between mid and the wall observed so far (bps) */
+  closestApproachBps: number
+}
+export interface WallReport {
+  bidWalls: Array<WallInfo>
+  askWalls: Array<WallInfo>
+  /** Likelihood (0.0 to 1.0) that bid-side walls are spoofed */
+  bidSpoofScore: number
+  /** Likelihood (0.0 to 1.0) that ask-side walls are spoofed */
+  askSpoofScore: number
+  /** Top-5 OFI with wall volume discounted by its spoof score */
+  adjustedOfi: number
+  /** Walls pulled while price was approaching them (lifetime, both sides) */
+  pulledOnApproach: number
+}
+export interface Position {
+  symbol: string
+  /** Signed quantity: positive long, negative short, 0.0 flat */
+  quantity: number
+  /** Weighted-average entry price of the open quantity */
+  entryPrice: number
+  currentPrice: number
+  /** Unrealised PnL at `current_price` */
+  pnl: number
+  /** PnL locked in by partial/full closes */
+  realizedPnl: number
+  /** Total trading fees paid (maker + taker) */
+  fees: number
+  makerFees: number
+  takerFees: number
+  /** Cumulative funding: positive received, negative paid */
+  funding: number
+  /** Strategy of the latest attributed fill */
+  strategyId?: string
+  /** `proof_hash` of the latest attributed fill's TradingSignal */
+  signalHash?: string
+}
+/** Ledger entry for one execution. Positions are derived from these. */
+export interface Fill {
+  id: string
+  symbol: string
+  /** "BUY" or "SELL" */
+  side: string
+  quantity: number
+  price: number
+  fee: number
+  /** "MAKER" or "TAKER" */
+  liquidity: string
+  timestampMs: number
+  /** `proof_hash` of the TradingSignal that originated the order */
+  signalHash?: string
+  strategyId?: string
+  /** "MathGuardian", "NeuralCortex" or "SymbolicConsensus" */
+  signalSource?: string
+}
+/** Periodic perpetual funding booked against a symbol. */
+export interface FundingPayment {
+  id: string
+  symbol: string
+  rate: number
+  /** Positive received, negative paid */
+  amount: number
+  timestampMs: number
+}
+/** Gross/fees/funding/net decomposition for one symbol or the whole book. */
+export interface PnlBreakdown {
+  /** Symbol, or "TOTAL" for the aggregate */
+  symbol: string
+  realizedPnl: number
+  unrealizedPnl: number
+  grossPnl: number
+  makerFees: number
+  takerFees: number
+  fees: number
+  funding: number
+  netPnl: number
+}
+export interface PnlReport {
+  positions: Array<PnlBreakdown>
+  total: PnlBreakdown
+}
+export interface FillFilter {
+  symbol?: string
+  side?: string
+  sinceMs?: number
+  untilMs?: number
+  signalHash?: string
+  strategyId?: string
+}
+/** Result of replaying the fill ledger against the stored positions. */
+export interface LedgerCheck {
+  /** Positions rebuilt purely from the ledger */
+  positions: Array<Position>
+  /** Symbols whose stored position disagrees with the replay */
+  mismatchedSymbols: Array<string>
+  matches: boolean
+}
+export interface PriceUpdate {
+  symbol: string
+  price: number
+}
+export interface PortfolioTotals {
+  /** Non-flat positions */
+  positionCount: number
+  /** Sum of absolute notionals at current prices */
+  grossExposure: number
+  /** Long notional minus short notional at current prices */
+  netExposure: number
+  unrealizedPnl: number
+  realizedPnl: number
+  fees: number
+  funding: number
+  /** Realised + unrealised - fees + funding */
+  netPnl: number
+}
+/** One position touched by a commit or an imported update. */
+export interface PositionChange {
+  symbol: string
+  /** Position fields whose value changed ("quantity", "current_price", "pnl", ...) */
+  changedFields: Array<string>
+  /** The position after the change */
+  position?: Position
+  /** "LOCAL" for edits made by this guardian, "IMPORT" for merged replica updates */
+  source: string
+}
+/** Typed view of the whole portfolio at one version. */
+export interface PortfolioSnapshot {
+  positions: Array<Position>
+  totals: PortfolioTotals
+  /** Unix ms of the latest recorded commit (None if nothing committed yet) */
+  lastUpdateMs?: number
+  /** Hex-encoded version the snapshot was read at */
+  version: string
+}
+/** Version of the portfolio document right after a local commit. */
+export interface CommitRecord {
+  timestampMs: number
+  /** Hex-encoded Loro frontiers, usable with `get_portfolio_at_version` */
+  version: string
+}
+/** A position as reported by the exchange. */
+export interface ExchangePosition {
+  symbol: string
+  /** Signed quantity: positive long, negative short */
+  quantity: number
+  entryPrice: number
+}
+export interface ReconcileOptions {
+  quantityTolerance?: number
+  entryPriceToleranceBps?: number
+  /** Overwrite guardian positions with exchange values (recorded in the ledger) */
+  adoptExchange?: boolean
+}
+export interface PositionMismatch {
+  symbol: string
+  /**
+   * "MISSING" (exchange only), "EXTRA" (guardian only),
+   * "QUANTITY_DRIFT" or "ENTRY_PRICE_DRIFT"
+   */
+  kind: string
+  guardianQuantity: number
+  exchangeQuantity: number
+  guardianEntryPrice: number
+  exchangeEntryPrice: number
+}
+export interface ReconcileReport {
+  mismatches: Array<PositionMismatch>
+  inSync: boolean
+  /** Symbols whose guardian position was replaced by the exchange values */
+  adjustedSymbols: Array<string>
+}
 export declare class TitanGuardian {
   constructor()
+  /**
+   * Restore the portfolio from a snapshot file (created on first checkpoint)
+   * and checkpoint to it every `checkpoint_interval_ms` while it has unsaved changes
+   * (default 5000, 0 = on-demand only).
+   * Throws if the existing snapshot is unreadable.
+   */
+  static open(snapshotPath: string, checkpointIntervalMs?: number | undefined | null): TitanGuardian
+  /** Write the portfolio snapshot to disk now. */
+  checkpoint(): void
   /**
    * Calculate Order Flow Imbalance (OFI) from raw Orderbook snapshot.
    * Uses Zero-Copy Buffer for maximum performance.
    * Input: JSON buffer of bids/asks, but parsed manually for speed.
    * For this version, we accept strings for simplicity, but Buffer is supported.
+   * Throws on malformed JSON, non-positive prices and negative sizes;
+   * returns 0.0 when both sides are empty.
    */
   calculateOfi(bidsJson: string, asksJson: string): number
+  /**
+   * Microstructure features (microprice, queue imbalance, weighted mid,
+   * decayed depth pressure, book slope) for a single Orderbook snapshot.
+   * `level_decay` defaults to 0.5 per level.
+   */
+  computeBookFeatures(bidsJson: string, asksJson: string, levelDecay?: number | undefined | null): BookFeatures
   /**
    * The "Silicon Guardian" Validation Gate
    * Validates AI intent against Datalog risk invariants.
    * Returns: { allowed: boolean, reason: string }
    */
   validateIntent(side: string, size: number, vol: number, ofi: number, trend: string, adx: number, rsi: number, positionCount: number): string
+  /**
+   * Track walls across snapshots for `symbol` and score how likely they are spoofed.
+   * Walls pulled as price approaches them raise the side's spoof score.
+   * Levels are matched on `tick_size` (default 1e-8); scores fade out
+   * when snapshots stop arriving.
+   */
+  trackWalls(symbol: string, bidsJson: string, asksJson: string, timestampMs: number, tickSize?: number | undefined | null): WallReport
+  /**
+   * Configure VPIN volume buckets for `symbol` (resets its flow history).
+   * `window_buckets` defaults to 50.
+   */
+  configureFlow(symbol: string, bucketVolume: number, windowBuckets?: number | undefined | null): void
+  /**
+   * Ingest one trade print. `side` is the aggressor ("BUY"/"SELL");
+   * when omitted it is inferred with the tick rule.
+   * Throws on a non-positive price or size or an unknown side.
+   */
+  ingestTrade(symbol: string, price: number, size: number, side?: string | undefined | null): void
+  /**
+   * Ingest a batch of trade prints in arrival order.
+   * Throws, ingesting none of them, if any print is invalid.
+   */
+  ingestTrades(symbol: string, trades: Array<TradePrint>): void
+  /** VPIN toxicity and signed trade flow for `symbol`. */
+  getFlowMetrics(symbol: string): FlowMetrics
+  /**
+   * Same gate as `validate_intent`, enriched with facts the guardian
+   * tracks for `symbol` (wall spoof scores, VPIN toxicity, margin usage and
+   * liquidation distance after the trade, open orders and resting notional).
+   * Trades that only shrink the position are exempt from the toxicity and
+   * order-exposure blocks.
+   */
+  validateIntentForSymbol(symbol: string, side: string, size: number, vol: number, ofi: number, trend: string, adx: number, rsi: number, positionCount: number): string
   updatePosition(symbol: string, quantity: number, price: number): void
-  getPortfolioState(): string
+  /**
+   * Book an execution (`side` "BUY"/"SELL") against the symbol's position:
+   * averages entries, realises PnL on partial/full closes and handles flips.
+   * The fill is appended to the ledger; an already-booked `fill_id` is ignored.
+   * `liquidity` is "MAKER" or "TAKER" (default) for fee attribution;
+   * `strategy_id` and `signal_source` ("MathGuardian", "NeuralCortex",
+   * "SymbolicConsensus") tag the fill for PnL attribution.
+   */
+  applyFill(symbol: string, side: string, quantity: number, price: number, fee?: number | undefined | null, signalHash?: string | undefined | null, fillId?: string | undefined | null, liquidity?: string | undefined | null, strategyId?: string | undefined | null, signalSource?: string | undefined | null): Position
+  /** Start tracking an order (state NEW) before sending it to the exchange. */
+  submitOrder(order: OrderInput): Order
+  /** The exchange accepted the order. A late ack after fills is a no-op. */
+  acknowledgeOrder(id: string): Order
+  cancelOrder(id: string, reason?: string | undefined | null): Order
+  /** The exchange refused the order (only before it was acknowledged). */
+  rejectOrder(id: string, reason?: string | undefined | null): Order
+  /**
+   * Book an execution against a tracked order. Updates the fill ledger and
+   * position, and moves the order to PARTIALLY_FILLED or FILLED. Executions
+   * that raced our cancel are still booked (a partial one leaves it CANCELLED).
+   * Throws on overfills and on FILLED or REJECTED orders.
+   */
+  fillOrder(id: string, quantity: number, price: number, fee?: number | undefined | null, liquidity?: string | undefined | null, fillId?: string | undefined | null): Order
+  getOrder(id: string): Order | null
+  /**
+   * Tracked orders, oldest first. `open_only` keeps NEW, ACKNOWLEDGED and
+   * PARTIALLY_FILLED orders.
+   */
+  getOrders(symbol?: string | undefined | null, openOnly?: boolean | undefined | null): Array<Order>
+  /** Ledger fills matching the optional filter, in booking order. */
+  getFills(filter?: FillFilter | undefined | null): Array<Fill>
+  /**
+   * Rebuild positions from the fill ledger and report any symbol whose
+   * stored position disagrees.
+   */
+  rebuildPositionsFromLedger(): LedgerCheck
+  getPosition(symbol: string): Position | null
+  /**
+   * Book a perpetual funding payment for `symbol`. `amount` (positive =
+   * received) defaults to `-rate * notional` at the current mark.
+   */
+  applyFunding(symbol: string, rate: number, amount?: number | undefined | null): FundingPayment
+  /** Funding payments booked for `symbol` (all symbols if omitted). */
+  getFundingPayments(symbol?: string | undefined | null): Array<FundingPayment>
+  /** Gross PnL, maker/taker fees, funding and net PnL per symbol and in total. */
+  getPnlBreakdown(): PnlReport
+  /**
+   * Set wallet balance, maintenance margin rate and margin risk limits.
+   * Omitted fields keep their current value. Returns the resulting settings.
+   */
+  configureAccount(config: AccountConfig): AccountSettings
+  /** Leverage used for `symbol`'s margin and liquidation estimates (default 1x). */
+  setLeverage(symbol: string, leverage: number): void
+  /**
+   * Equity, used and free margin, margin usage and estimated liquidation
+   * prices at current marks.
+   */
+  getAccountState(): AccountState
+  /**
+   * Realised/unrealised PnL per strategy, signal source and symbol,
+   * replaying each strategy's fills as a separate book.
+   */
+  getPnlAttribution(): AttributionReport
+  /** Recorded equity curve (wallet balance + settled + unrealised PnL), oldest first. */
+  getEquityCurve(): Array<EquityPoint>
+  /**
+   * Returns, high-water mark, drawdowns, Sharpe/Sortino and closed-trade
+   * statistics (win rate, profit factor, average duration) over the window.
+   */
+  getPerformanceAnalytics(options?: AnalyticsOptions | undefined | null): PerformanceReport
+  /**
+   * Emergency flatten: reduce-only close orders for every open position,
+   * rounded to each instrument's lot/tick size and ordered by risk
+   * (closest to liquidation first), plus tracked open orders to cancel.
+   */
+  planFlattenAll(instruments?: Array<InstrumentSpec> | undefined | null, options?: FlattenOptions | undefined | null): FlattenPlan
+  /**
+   * Compare guardian positions against the exchange's position list.
+   * Reports missing/extra symbols and quantity or entry-price drift beyond
+   * tolerance; with `adoptExchange` the exchange values replace ours and the
+   * adjustment is booked in the fill ledger.
+   */
+  reconcilePositions(exchangePositions: Array<ExchangePosition>, options?: ReconcileOptions | undefined | null): ReconcileReport
+  /** Mark a position to market. Returns false if no position is held in `symbol`. */
+  updatePrice(symbol: string, price: number): boolean
+  /**
+   * Mark several positions to market in one commit.
+   * Returns how many held positions were revalued.
+   */
+  updatePrices(updates: Array<PriceUpdate>): number
+  /** Exposure and unrealised PnL across all positions. */
+  getPortfolioTotals(): PortfolioTotals
+  /** Encoded version vector of this replica, to send to peers for `exportUpdates`. */
+  getVersion(): Buffer
+  /** Portfolio updates a peer at version `since` is missing (all history if omitted). */
+  exportUpdates(since?: Buffer | undefined | null): Buffer
+  /**
+   * Merge updates from another replica (bot process or monitoring UI).
+   * Returns false if some updates are waiting on missing history.
+   */
+  importUpdates(updates: Buffer): boolean
+  /**
+   * Hex-encoded version of the current portfolio. Log it with trade approvals
+   * to replay what the guardian believed via `get_portfolio_at_version`.
+   */
+  getCurrentVersion(): string
+  /** Recorded portfolio commits (timestamp + version), oldest first. Price marks are not recorded. */
+  getCommitHistory(): Array<CommitRecord>
+  /** Positions as they were at a recorded version. */
+  getPortfolioAtVersion(version: string): Array<Position>
+  /** Positions as the guardian believed them at `timestamp_ms` (unix ms). */
+  getPortfolioAtTimestamp(timestampMs: number): Array<Position>
+  /**
+   * Call `callback` with the changed positions after every commit or replica
+   * import that touches them (quantity, price, PnL, fees, funding).
+   * Returns an id for `unsubscribe_portfolio`.
+   */
+  subscribePortfolio(callback: (changes: Array<PositionChange>) => void): number
+  /** Stop a `subscribe_portfolio` callback. Returns false for unknown ids. */
+  unsubscribePortfolio(id: number): boolean
+  /** Positions with aggregate exposure/PnL totals and the last update time. */
+  getPortfolioState(): PortfolioSnapshot
+  /**
+   * Raw Loro value of the positions map as JSON. Debugging only; the shape
+   * follows the document layout and may change.
+   */
+  dumpPortfolioState(): string
 }
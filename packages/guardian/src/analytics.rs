@@ -0,0 +1,402 @@
+use crate::error::{GuardianError, Result};
+use crate::state::{Fill, Liquidity, Position, Side};
+use napi_derive::napi;
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Equity is resampled to the last value per bucket of this size (1h)
+pub const DEFAULT_SAMPLE_INTERVAL_MS: i64 = 3_600_000;
+/// Samples spanned by each rolling return (24 x 1h = daily)
+pub const DEFAULT_ROLLING_WINDOW: u32 = 24;
+/// Upper bound on resampled buckets (curve span / sample interval)
+const MAX_SAMPLES: i64 = 1_000_000;
+/// Crypto trades around the clock: annualise over 365 full days
+const YEAR_MS: f64 = 365.0 * 24.0 * 3_600_000.0;
+
+/// Account equity recorded after a portfolio commit.
+#[napi(object)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct EquityPoint {
+    pub timestamp_ms: i64,
+    pub equity: f64,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct AnalyticsOptions {
+    /// Only equity points and trades closed at or after this time (unix ms)
+    pub since_ms: Option<i64>,
+    pub until_ms: Option<i64>,
+    /// Resampling bucket for returns (default 1h)
+    pub sample_interval_ms: Option<i64>,
+    /// Samples per rolling return (default 24)
+    pub rolling_window: Option<u32>,
+}
+
+impl AnalyticsOptions {
+    fn contains(&self, timestamp_ms: i64) -> bool {
+        self.since_ms.is_none_or(|t| timestamp_ms >= t) && self.until_ms.is_none_or(|t| timestamp_ms <= t)
+    }
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct PerformanceReport {
+    /// Resampled equity points the return statistics are based on (one per
+    /// interval, gaps carry the last equity forward)
+    pub samples: u32,
+    pub start_equity: f64,
+    pub end_equity: f64,
+    pub high_water_mark: f64,
+    /// Current drawdown from the high-water mark (fraction)
+    pub drawdown: f64,
+    pub max_drawdown: f64,
+    pub total_return: f64,
+    /// Return over each `rolling_window` samples, oldest first
+    pub rolling_returns: Vec<f64>,
+    /// Annualised from per-sample returns
+    pub sharpe: f64,
+    pub sortino: f64,
+    /// Round trips (open to flat, or open to flip) closed in the window
+    pub trade_count: u32,
+    pub win_rate: f64,
+    /// Gross profit / gross loss of closed trades (Infinity without losses)
+    pub profit_factor: f64,
+    pub avg_trade_duration_ms: f64,
+}
+
+/// A round trip reconstructed from the fill ledger. PnL is net of fees.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosedTrade {
+    pub symbol: String,
+    pub opened_ms: i64,
+    pub closed_ms: i64,
+    pub pnl: f64,
+}
+
+struct OpenTrade {
+    position: Position,
+    opened_ms: i64,
+    realized_at_open: f64,
+    fees_at_open: f64,
+}
+
+/// Replay the ledger and cut it into round trips: a trade opens when the
+/// position leaves flat and closes when it returns to flat or flips.
+pub fn closed_trades(fills: &[Fill]) -> Vec<ClosedTrade> {
+    let mut open: HashMap<&str, OpenTrade> = HashMap::new();
+    let mut closed = Vec::new();
+
+    for fill in fills {
+        let Some(side) = Side::parse(&fill.side) else { continue };
+        let liquidity = Liquidity::parse(&fill.liquidity).unwrap_or_default();
+        let trade = open.entry(fill.symbol.as_str()).or_insert_with(|| OpenTrade {
+            position: Position::default(),
+            opened_ms: fill.timestamp_ms,
+            realized_at_open: 0.0,
+            fees_at_open: 0.0,
+        });
+
+        let was_flat = trade.position.is_flat();
+        let previous_sign = trade.position.quantity.signum();
+        if was_flat {
+            trade.opened_ms = fill.timestamp_ms;
+            trade.realized_at_open = trade.position.realized_pnl;
+            trade.fees_at_open = trade.position.fees;
+        }
+        trade
+            .position
+            .apply_fill(side, fill.quantity, fill.price, fill.fee, liquidity);
+
+        let position = &trade.position;
+        if !was_flat && (position.is_flat() || position.quantity.signum() != previous_sign) {
+            closed.push(ClosedTrade {
+                symbol: fill.symbol.clone(),
+                opened_ms: trade.opened_ms,
+                closed_ms: fill.timestamp_ms,
+                pnl: (position.realized_pnl - trade.realized_at_open) - (position.fees - trade.fees_at_open),
+            });
+            // A flip opens the next trade at the same fill
+            trade.opened_ms = fill.timestamp_ms;
+            trade.realized_at_open = position.realized_pnl;
+            trade.fees_at_open = position.fees;
+        }
+    }
+    closed
+}
+
+pub fn performance(
+    curve: &[EquityPoint],
+    trades: &[ClosedTrade],
+    options: &AnalyticsOptions,
+) -> Result<PerformanceReport> {
+    let mut report = PerformanceReport::default();
+    equity_stats(curve, options, &mut report)?;
+    trade_stats(trades, options, &mut report)?;
+    Ok(report)
+}
+
+fn equity_stats(curve: &[EquityPoint], options: &AnalyticsOptions, report: &mut PerformanceReport) -> Result<()> {
+    let interval = options
+        .sample_interval_ms
+        .filter(|i| *i > 0)
+        .unwrap_or(DEFAULT_SAMPLE_INTERVAL_MS);
+    let window = options.rolling_window.unwrap_or(DEFAULT_ROLLING_WINDOW).max(1) as i64;
+
+    let (timestamps, equity): (Vec<i64>, Vec<f64>) = curve
+        .iter()
+        .filter(|p| options.contains(p.timestamp_ms))
+        .map(|p| (p.timestamp_ms, p.equity))
+        .unzip();
+    if equity.is_empty() {
+        return Ok(());
+    }
+
+    // Returns and drawdowns are undefined off a non-positive base (wallet
+    // balance not set); those samples are left null
+    let simple_return = |lag: i64| {
+        let base = col("equity").shift(lit(lag));
+        when(base.clone().gt(lit(0.0)))
+            .then(col("equity") / base - lit(1.0))
+            .otherwise(lit(Null {}))
+    };
+
+    // Every bucket from the first to the last sample, so a gap in the curve
+    // counts as flat intervals rather than one long one
+    let bucket = |t: i64| t - t.rem_euclid(interval);
+    let first = timestamps.iter().copied().min().map_or(0, bucket);
+    let last = timestamps.iter().copied().max().map_or(0, bucket);
+    if (last - first) / interval >= MAX_SAMPLES {
+        return Err(GuardianError::Validation(format!(
+            "Sample interval of {} ms yields more than {} samples",
+            interval, MAX_SAMPLES
+        )));
+    }
+    let grid: Vec<i64> = (first..=last).step_by(interval as usize).collect();
+
+    let buckets = df!("timestamp_ms" => timestamps, "equity" => equity)?
+        .lazy()
+        .sort("timestamp_ms", SortOptions::default())
+        .group_by_stable([(col("timestamp_ms") - col("timestamp_ms") % lit(interval)).alias("bucket")])
+        .agg([col("equity").last()]);
+    let sampled = df!("bucket" => grid)?
+        .lazy()
+        .left_join(buckets, col("bucket"), col("bucket"))
+        .with_column(col("equity").forward_fill(None))
+        .with_columns([
+            simple_return(1).alias("return"),
+            simple_return(window).alias("rolling_return"),
+            col("equity").cum_max(false).alias("high_water_mark"),
+        ])
+        .with_column(
+            when(col("high_water_mark").gt(lit(0.0)))
+                .then((col("high_water_mark") - col("equity")) / col("high_water_mark"))
+                .otherwise(lit(Null {}))
+                .alias("drawdown"),
+        )
+        .collect()?;
+
+    let downside = when(col("return").lt(lit(0.0)))
+        .then(col("return"))
+        .otherwise(lit(0.0));
+    let stats = sampled
+        .clone()
+        .lazy()
+        .filter(col("return").is_not_null())
+        .select([
+            col("return").mean().alias("mean"),
+            col("return").std(1).alias("std"),
+            (downside.clone() * downside).mean().alias("downside_var"),
+        ])
+        .collect()?;
+
+    let equity = sampled.column("equity")?.f64()?;
+    let high_water_mark = sampled.column("high_water_mark")?.f64()?;
+    let drawdown = sampled.column("drawdown")?.f64()?;
+
+    report.samples = sampled.height() as u32;
+    report.start_equity = equity.get(0).unwrap_or(0.0);
+    report.end_equity = equity.get(equity.len() - 1).unwrap_or(0.0);
+    report.high_water_mark = high_water_mark.get(high_water_mark.len() - 1).unwrap_or(0.0);
+    report.drawdown = drawdown.get(drawdown.len() - 1).filter(|d| d.is_finite()).unwrap_or(0.0);
+    report.max_drawdown = drawdown.into_iter().flatten().filter(|d| d.is_finite()).fold(0.0, f64::max);
+    if report.start_equity > 0.0 {
+        report.total_return = report.end_equity / report.start_equity - 1.0;
+    }
+    report.rolling_returns = sampled.column("rolling_return")?.f64()?.into_iter().flatten().collect();
+
+    let annualise = (YEAR_MS / interval as f64).sqrt();
+    let mean = scalar(&stats, "mean");
+    let (std, downside_dev) = (scalar(&stats, "std"), scalar(&stats, "downside_var").sqrt());
+    report.sharpe = if std > 0.0 { mean / std * annualise } else { 0.0 };
+    report.sortino = if downside_dev > 0.0 { mean / downside_dev * annualise } else { 0.0 };
+    Ok(())
+}
+
+fn trade_stats(trades: &[ClosedTrade], options: &AnalyticsOptions, report: &mut PerformanceReport) -> Result<()> {
+    let (pnl, duration): (Vec<f64>, Vec<f64>) = trades
+        .iter()
+        .filter(|t| options.contains(t.closed_ms))
+        .map(|t| (t.pnl, (t.closed_ms - t.opened_ms) as f64))
+        .unzip();
+    if pnl.is_empty() {
+        return Ok(());
+    }
+
+    let stats = df!("pnl" => pnl, "duration_ms" => duration)?
+        .lazy()
+        .select([
+            col("pnl").gt(lit(0.0)).cast(DataType::Float64).mean().alias("win_rate"),
+            when(col("pnl").gt(lit(0.0))).then(col("pnl")).otherwise(lit(0.0)).sum().alias("gross_profit"),
+            when(col("pnl").lt(lit(0.0))).then(lit(0.0) - col("pnl")).otherwise(lit(0.0)).sum().alias("gross_loss"),
+            col("duration_ms").mean().alias("avg_duration"),
+            col("pnl").count().alias("count"),
+        ])
+        .collect()?;
+
+    let (gross_profit, gross_loss) = (scalar(&stats, "gross_profit"), scalar(&stats, "gross_loss"));
+    report.trade_count = stats.column("count")?.u32()?.get(0).unwrap_or(0);
+    report.win_rate = scalar(&stats, "win_rate");
+    report.avg_trade_duration_ms = scalar(&stats, "avg_duration");
+    report.profit_factor = match (gross_profit > 0.0, gross_loss > 0.0) {
+        (_, true) => gross_profit / gross_loss,
+        (true, false) => f64::INFINITY,
+        (false, false) => 0.0,
+    };
+    Ok(())
+}
+
+fn scalar(df: &DataFrame, name: &str) -> f64 {
+    df.column(name)
+        .ok()
+        .and_then(|s| s.f64().ok()?.get(0))
+        .filter(|v| v.is_finite())
+        .unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(side: &str, quantity: f64, price: f64, timestamp_ms: i64) -> Fill {
+        Fill {
+            symbol: "BTC".to_string(),
+            side: side.to_string(),
+            quantity,
+            price,
+            fee: 1.0,
+            liquidity: "TAKER".to_string(),
+            timestamp_ms,
+            ..Fill::default()
+        }
+    }
+
+    #[test]
+    fn test_round_trips_split_on_flat_and_flip() {
+        let fills = [
+            fill("BUY", 1.0, 100.0, 0),
+            fill("SELL", 1.0, 110.0, 1_000),
+            // Short 1 then flip to long 1: the flip closes the short
+            fill("SELL", 1.0, 110.0, 2_000),
+            fill("BUY", 2.0, 120.0, 5_000),
+            fill("SELL", 1.0, 125.0, 6_000),
+        ];
+        let trades = closed_trades(&fills);
+
+        assert_eq!(trades.len(), 3);
+        assert_eq!(trades[0].pnl, 10.0 - 2.0);
+        assert_eq!(trades[1].pnl, -10.0 - 2.0);
+        assert_eq!((trades[1].opened_ms, trades[1].closed_ms), (2_000, 5_000));
+        // The flip fill's fee is charged to the trade it closes
+        assert_eq!(trades[2].pnl, 5.0 - 1.0);
+        assert_eq!(trades[2].opened_ms, 5_000);
+    }
+
+    #[test]
+    fn test_drawdown_and_trade_stats() {
+        let hour = 3_600_000;
+        let curve: Vec<EquityPoint> = [100.0, 120.0, 90.0, 110.0]
+            .iter()
+            .enumerate()
+            .map(|(i, equity)| EquityPoint {
+                timestamp_ms: i as i64 * hour,
+                equity: *equity,
+            })
+            .collect();
+        let trades = [
+            ClosedTrade { symbol: "BTC".to_string(), opened_ms: 0, closed_ms: 2_000, pnl: 30.0 },
+            ClosedTrade { symbol: "BTC".to_string(), opened_ms: 0, closed_ms: 4_000, pnl: -10.0 },
+        ];
+        let options = AnalyticsOptions {
+            rolling_window: Some(2),
+            ..AnalyticsOptions::default()
+        };
+
+        let report = performance(&curve, &trades, &options).unwrap();
+        assert_eq!(report.samples, 4);
+        assert_eq!(report.high_water_mark, 120.0);
+        assert!((report.max_drawdown - 0.25).abs() < 1e-12);
+        assert!((report.total_return - 0.1).abs() < 1e-12);
+        assert_eq!(report.rolling_returns.len(), 2);
+        assert!((report.rolling_returns[0] - (-0.1)).abs() < 1e-12);
+
+        assert_eq!(report.trade_count, 2);
+        assert_eq!(report.win_rate, 0.5);
+        assert_eq!(report.profit_factor, 3.0);
+        assert_eq!(report.avg_trade_duration_ms, 3_000.0);
+    }
+
+    #[test]
+    fn test_non_positive_base_yields_no_returns() {
+        let hour = 3_600_000;
+        // No wallet balance configured: equity is booked PnL only
+        let curve: Vec<EquityPoint> = [0.0, -5.0, -10.0, 20.0, 10.0]
+            .iter()
+            .enumerate()
+            .map(|(i, equity)| EquityPoint {
+                timestamp_ms: i as i64 * hour,
+                equity: *equity,
+            })
+            .collect();
+
+        let report = performance(&curve, &[], &AnalyticsOptions::default()).unwrap();
+        assert_eq!(report.total_return, 0.0);
+        assert!((report.drawdown - 0.5).abs() < 1e-12);
+        assert!((report.max_drawdown - 0.5).abs() < 1e-12);
+        // Only the 20 -> 10 return has a positive base
+        assert_eq!(report.sharpe, 0.0);
+        assert!(report.sortino.is_finite());
+    }
+
+    #[test]
+    fn test_gaps_are_forward_filled() {
+        let hour = 3_600_000;
+        // No samples for hours 2-4: equity held at 110 over the gap
+        let curve = [(0, 100.0), (1, 110.0), (5, 121.0), (6, 133.1)]
+            .map(|(h, equity)| EquityPoint { timestamp_ms: h * hour, equity });
+        let options = AnalyticsOptions {
+            rolling_window: Some(2),
+            ..AnalyticsOptions::default()
+        };
+
+        let report = performance(&curve, &[], &options).unwrap();
+        assert_eq!(report.samples, 7);
+        assert!((report.total_return - 0.331).abs() < 1e-12);
+        // Rolling returns span 2 hours each, including across the gap
+        assert_eq!(report.rolling_returns.len(), 5);
+        assert!((report.rolling_returns[0] - 0.1).abs() < 1e-12);
+        assert!(report.rolling_returns[1..3].iter().all(|r| r.abs() < 1e-12));
+
+        // Hourly returns 0.1, 0, 0, 0, 0.1, 0.1: mean 0.05, sample std sqrt(0.003)
+        let sharpe = 0.05 / 0.003_f64.sqrt() * (24.0 * 365.0_f64).sqrt();
+        assert!((report.sharpe / sharpe - 1.0).abs() < 1e-9);
+
+        let too_fine = AnalyticsOptions {
+            sample_interval_ms: Some(1),
+            ..AnalyticsOptions::default()
+        };
+        assert!(performance(&curve, &[], &too_fine).is_err());
+    }
+}
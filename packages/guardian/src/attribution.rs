@@ -0,0 +1,146 @@
+use crate::state::{Fill, Liquidity, Position, Side};
+use napi_derive::napi;
+use std::collections::BTreeMap;
+
+/// Key used for fills booked without a strategy or signal source
+pub const UNATTRIBUTED: &str = "UNATTRIBUTED";
+
+/// PnL of one (strategy, signal source, symbol) book.
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct AttributionBook {
+    pub strategy_id: String,
+    pub signal_source: String,
+    pub symbol: String,
+    /// Net quantity this book's fills leave open
+    pub quantity: f64,
+    pub realized_pnl: f64,
+    /// Open quantity valued at the symbol's current mark
+    pub unrealized_pnl: f64,
+    pub fees: f64,
+    /// Realised + unrealised - fees
+    pub net_pnl: f64,
+    pub fill_count: u32,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct AttributionLine {
+    /// Strategy ID, signal source or symbol, depending on the breakdown
+    pub key: String,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub fees: f64,
+    pub net_pnl: f64,
+    pub fill_count: u32,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct AttributionReport {
+    pub books: Vec<AttributionBook>,
+    pub by_strategy: Vec<AttributionLine>,
+    pub by_source: Vec<AttributionLine>,
+    pub by_symbol: Vec<AttributionLine>,
+}
+
+/// Replay the ledger with each (strategy, source, symbol) as its own book,
+/// so offsetting fills from different strategies are each charged to their
+/// own strategy rather than netted away. Funding is not attributed.
+pub fn attribute(fills: &[Fill], mark_of: impl Fn(&str) -> Option<f64>) -> AttributionReport {
+    let mut books: BTreeMap<(String, String, String), (Position, u32)> = BTreeMap::new();
+
+    for fill in fills {
+        let Some(side) = Side::parse(&fill.side) else { continue };
+        let liquidity = Liquidity::parse(&fill.liquidity).unwrap_or_default();
+        let key = (
+            fill.strategy_id.clone().unwrap_or_else(|| UNATTRIBUTED.to_string()),
+            fill.signal_source.clone().unwrap_or_else(|| UNATTRIBUTED.to_string()),
+            fill.symbol.clone(),
+        );
+        let (book, count) = books.entry(key).or_default();
+        book.apply_fill(side, fill.quantity, fill.price, fill.fee, liquidity);
+        *count += 1;
+    }
+
+    let mut report = AttributionReport::default();
+    for ((strategy_id, signal_source, symbol), (mut book, fill_count)) in books {
+        if let Some(mark) = mark_of(&symbol) {
+            book.current_price = mark;
+        }
+        let unrealized_pnl = if book.is_flat() { 0.0 } else { book.unrealized_pnl() };
+
+        report.books.push(AttributionBook {
+            net_pnl: book.realized_pnl + unrealized_pnl - book.fees,
+            strategy_id,
+            signal_source,
+            symbol,
+            quantity: book.quantity,
+            realized_pnl: book.realized_pnl,
+            unrealized_pnl,
+            fees: book.fees,
+            fill_count,
+        });
+    }
+
+    report.by_strategy = roll_up(&report.books, |b| &b.strategy_id);
+    report.by_source = roll_up(&report.books, |b| &b.signal_source);
+    report.by_symbol = roll_up(&report.books, |b| &b.symbol);
+    report
+}
+
+fn roll_up(books: &[AttributionBook], key_of: impl Fn(&AttributionBook) -> &String) -> Vec<AttributionLine> {
+    let mut lines: BTreeMap<&String, AttributionLine> = BTreeMap::new();
+    for book in books {
+        let line = lines.entry(key_of(book)).or_insert_with(|| AttributionLine {
+            key: key_of(book).clone(),
+            ..AttributionLine::default()
+        });
+        line.realized_pnl += book.realized_pnl;
+        line.unrealized_pnl += book.unrealized_pnl;
+        line.fees += book.fees;
+        line.net_pnl += book.net_pnl;
+        line.fill_count += book.fill_count;
+    }
+    lines.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(strategy: Option<&str>, source: &str, side: &str, quantity: f64, price: f64) -> Fill {
+        Fill {
+            symbol: "BTC".to_string(),
+            side: side.to_string(),
+            quantity,
+            price,
+            liquidity: "TAKER".to_string(),
+            strategy_id: strategy.map(str::to_string),
+            signal_source: Some(source.to_string()),
+            ..Fill::default()
+        }
+    }
+
+    #[test]
+    fn test_offsetting_strategies_are_attributed_separately() {
+        let fills = [
+            fill(Some("momentum"), "MathGuardian", "BUY", 1.0, 100.0),
+            fill(Some("reversion"), "NeuralCortex", "SELL", 1.0, 100.0),
+            fill(Some("momentum"), "MathGuardian", "SELL", 0.5, 120.0),
+            fill(None, "MathGuardian", "BUY", 1.0, 110.0),
+        ];
+        let report = attribute(&fills, |_| Some(110.0));
+
+        let strategy = |key: &str| report.by_strategy.iter().find(|l| l.key == key).unwrap();
+        assert_eq!(strategy("momentum").realized_pnl, 10.0);
+        assert_eq!(strategy("momentum").unrealized_pnl, 5.0);
+        assert_eq!(strategy("reversion").unrealized_pnl, -10.0);
+        assert_eq!(strategy(UNATTRIBUTED).fill_count, 1);
+
+        let math = report.by_source.iter().find(|l| l.key == "MathGuardian").unwrap();
+        assert_eq!(math.net_pnl, 15.0);
+        assert_eq!(report.by_symbol.len(), 1);
+        assert_eq!(report.by_symbol[0].net_pnl, 5.0);
+    }
+}
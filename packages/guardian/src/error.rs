@@ -0,0 +1,101 @@
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
+
+pub type Result<T, E = GuardianError> = std::result::Result<T, E>;
+
+/// Every failure the guardian reports to JS. Surfaced as a thrown `Error`
+/// whose message starts with the kind, e.g. `[VALIDATION] Invalid side: UP`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GuardianError {
+    /// Bad caller input (unknown side, non-positive quantity, malformed version)
+    Validation(String),
+    /// The request conflicts with current state (unknown order, illegal transition)
+    State(String),
+    /// Encoding, decoding or persisting the portfolio document failed
+    Serialization(String),
+    /// A lock was poisoned by a panic while it was held
+    Lock(String),
+}
+
+impl GuardianError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GuardianError::Validation(_) => "VALIDATION",
+            GuardianError::State(_) => "STATE",
+            GuardianError::Serialization(_) => "SERIALIZATION",
+            GuardianError::Lock(_) => "LOCK",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            GuardianError::Validation(m)
+            | GuardianError::State(m)
+            | GuardianError::Serialization(m)
+            | GuardianError::Lock(m) => m,
+        }
+    }
+}
+
+impl fmt::Display for GuardianError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.kind(), self.message())
+    }
+}
+
+impl std::error::Error for GuardianError {}
+
+impl From<GuardianError> for napi::Error {
+    fn from(error: GuardianError) -> Self {
+        let status = match error {
+            GuardianError::Validation(_) => napi::Status::InvalidArg,
+            _ => napi::Status::GenericFailure,
+        };
+        napi::Error::new(status, error.to_string())
+    }
+}
+
+impl From<loro::LoroError> for GuardianError {
+    fn from(error: loro::LoroError) -> Self {
+        GuardianError::State(format!("Portfolio document update failed: {}", error))
+    }
+}
+
+impl From<serde_json::Error> for GuardianError {
+    fn from(error: serde_json::Error) -> Self {
+        GuardianError::Serialization(error.to_string())
+    }
+}
+
+impl From<polars::prelude::PolarsError> for GuardianError {
+    fn from(error: polars::prelude::PolarsError) -> Self {
+        GuardianError::State(format!("Analytics computation failed: {}", error))
+    }
+}
+
+/// Lock `mutex`, reporting poisoning instead of panicking.
+pub fn lock<'a, T>(mutex: &'a Mutex<T>, name: &str) -> Result<MutexGuard<'a, T>> {
+    mutex
+        .lock()
+        .map_err(|_| GuardianError::Lock(format!("{} lock poisoned by an earlier panic", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poisoned_lock_is_an_error() {
+        let mutex = Mutex::new(0);
+        let _ = std::panic::catch_unwind(|| {
+            let _guard = mutex.lock().unwrap();
+            panic!("poison");
+        });
+
+        let error = lock(&mutex, "Portfolio").unwrap_err();
+        assert_eq!(error.kind(), "LOCK");
+        let js: napi::Error = error.into();
+        assert_eq!(js.status, napi::Status::GenericFailure);
+        assert!(js.reason.starts_with("[LOCK] Portfolio"));
+    }
+}
@@ -0,0 +1,117 @@
+use crate::state::Position;
+use loro::event::{Diff, DiffEvent};
+use loro::{ContainerID, EventTriggerKind, Index};
+use napi_derive::napi;
+
+/// One position touched by a commit or an imported update.
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct PositionChange {
+    pub symbol: String,
+    /// Position fields whose value changed ("quantity", "current_price", "pnl", ...)
+    pub changed_fields: Vec<String>,
+    /// The position after the change
+    pub position: Option<Position>,
+    /// "LOCAL" for edits made by this guardian, "IMPORT" for merged replica updates
+    pub source: String,
+}
+
+/// Receives every batch of position changes. Runs while the portfolio is
+/// locked, so it must hand the changes off rather than call back into it.
+pub type Listener = Box<dyn Fn(&[PositionChange]) + Send>;
+
+/// Collects position changes from Loro document events and fans them out
+/// to subscribed listeners once the commit (or import) has completed.
+#[derive(Default)]
+pub struct ChangeFeed {
+    next_id: u32,
+    listeners: Vec<(u32, Listener)>,
+    // Filled from inside Loro's event callback, drained after the commit
+    pending: Vec<PositionChange>,
+}
+
+impl ChangeFeed {
+    pub fn subscribe(&mut self, listener: Listener) -> u32 {
+        self.next_id += 1;
+        self.listeners.push((self.next_id, listener));
+        self.next_id
+    }
+
+    pub fn unsubscribe(&mut self, id: u32) -> bool {
+        let before = self.listeners.len();
+        self.listeners.retain(|(listener_id, _)| *listener_id != id);
+        self.listeners.len() != before
+    }
+
+    /// Note which positions (and fields) a Loro event touched.
+    /// Checkouts are time-travel reads, not changes, and are skipped.
+    pub fn record(&mut self, event: &DiffEvent) {
+        let source = match event.triggered_by {
+            EventTriggerKind::Local => "LOCAL",
+            EventTriggerKind::Import => "IMPORT",
+            EventTriggerKind::Checkout => return,
+        };
+        if self.listeners.is_empty() {
+            return;
+        }
+
+        for diff in &event.events {
+            let Some((ContainerID::Root { name, .. }, _)) = diff.path.first() else { continue };
+            if name.as_str() != "positions" {
+                continue;
+            }
+            let Diff::Map(delta) = &diff.diff else { continue };
+
+            match diff.path.get(1) {
+                // Field edits inside one position's nested map
+                Some((_, Index::Key(symbol))) => {
+                    let fields = delta.updated.keys().map(|k| k.to_string());
+                    self.pending_for(symbol, source).changed_fields.extend(fields);
+                }
+                // New symbols in the positions map itself
+                None => {
+                    for symbol in delta.updated.keys() {
+                        self.pending_for(symbol, source);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Fill in the current positions and deliver pending changes.
+    pub fn flush(&mut self, read_position: impl Fn(&str) -> Option<Position>) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let mut changes = std::mem::take(&mut self.pending);
+        for change in changes.iter_mut() {
+            change.changed_fields.sort();
+            change.changed_fields.dedup();
+            change.position = read_position(&change.symbol);
+        }
+        for (_, listener) in &self.listeners {
+            listener(&changes);
+        }
+    }
+
+    fn pending_for(&mut self, symbol: &str, source: &str) -> &mut PositionChange {
+        let index = match self
+            .pending
+            .iter()
+            .position(|c| c.symbol == symbol && c.source == source)
+        {
+            Some(i) => i,
+            None => {
+                self.pending.push(PositionChange {
+                    symbol: symbol.to_string(),
+                    changed_fields: Vec::new(),
+                    position: None,
+                    source: source.to_string(),
+                });
+                self.pending.len() - 1
+            }
+        };
+        &mut self.pending[index]
+    }
+}
@@ -0,0 +1,332 @@
+use crate::error::{GuardianError, Result};
+use crate::margin::PositionMargin;
+use crate::orders::Order;
+use crate::state::Position;
+use napi_derive::napi;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+
+/// Aggressive limit orders cross the mark by this much unless configured
+pub const DEFAULT_SLIPPAGE_BPS: f64 = 50.0;
+
+/// Exchange trading rules for one symbol.
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct InstrumentSpec {
+    pub symbol: String,
+    /// Price increment
+    pub tick_size: f64,
+    /// Quantity increment
+    pub lot_size: f64,
+    pub min_quantity: Option<f64>,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct FlattenOptions {
+    /// "MARKET" (default) or "LIMIT" (aggressive limit through the mark;
+    /// positions without a mark are skipped)
+    pub order_type: Option<String>,
+    /// How far through the mark aggressive limits are priced (default 50bps)
+    pub slippage_bps: Option<f64>,
+    /// Only flatten these symbols (all positions when omitted)
+    pub symbols: Option<Vec<String>>,
+}
+
+/// One reduce-only close order, ready to submit as-is.
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct CloseOrder {
+    /// Submission order: most at-risk positions first
+    pub sequence: u32,
+    pub client_order_id: String,
+    pub symbol: String,
+    /// "BUY" closes a short, "SELL" closes a long
+    pub side: String,
+    pub quantity: f64,
+    /// Position left open by rounding `quantity` down to the lot size
+    pub dust: f64,
+    /// "MARKET" or "LIMIT"
+    pub order_type: String,
+    /// Limit price, rounded to the tick away from the mark
+    pub price: Option<f64>,
+    pub reduce_only: bool,
+    pub notional: f64,
+    pub unrealized_pnl: f64,
+    /// |mark - liquidation| / mark of the position being closed
+    pub liquidation_distance: Option<f64>,
+}
+
+/// A position the plan could not close, and why.
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct SkippedClose {
+    pub symbol: String,
+    pub quantity: f64,
+    pub reason: String,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct FlattenPlan {
+    /// Tracked open orders to cancel before submitting the closes
+    pub cancel_order_ids: Vec<String>,
+    pub orders: Vec<CloseOrder>,
+    pub skipped: Vec<SkippedClose>,
+    pub created_ms: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CloseType {
+    Market,
+    Limit,
+}
+
+/// Close every non-flat position. Orders are ranked by liquidation distance
+/// (closest first), then by absolute notional, then by unrealised loss.
+pub fn plan(
+    positions: &[Position],
+    margins: &[PositionMargin],
+    open_orders: &[Order],
+    instruments: &[InstrumentSpec],
+    options: &FlattenOptions,
+    timestamp_ms: i64,
+) -> Result<FlattenPlan> {
+    let close_type = match options.order_type.as_deref().map(str::to_ascii_uppercase).as_deref() {
+        None | Some("MARKET") => CloseType::Market,
+        Some("LIMIT") => CloseType::Limit,
+        Some(other) => return Err(GuardianError::Validation(format!("Invalid order type: {}", other))),
+    };
+    let slippage_bps = options.slippage_bps.unwrap_or(DEFAULT_SLIPPAGE_BPS);
+    if !(0.0..10_000.0).contains(&slippage_bps) {
+        return Err(GuardianError::Validation("Slippage must be between 0 and 10000 bps".to_string()));
+    }
+    let slippage = to_decimal(slippage_bps)? / Decimal::from(10_000);
+    let selected = |symbol: &str| options.symbols.as_ref().is_none_or(|s| s.iter().any(|x| x == symbol));
+
+    let mut plan = FlattenPlan {
+        cancel_order_ids: open_orders
+            .iter()
+            .filter(|o| selected(&o.symbol))
+            .map(|o| o.id.clone())
+            .collect(),
+        created_ms: timestamp_ms,
+        ..FlattenPlan::default()
+    };
+
+    let mut at_risk: Vec<(&Position, Option<f64>)> = positions
+        .iter()
+        .filter(|p| !p.is_flat() && selected(&p.symbol))
+        .map(|p| {
+            let distance = margins
+                .iter()
+                .find(|m| m.symbol == p.symbol)
+                .and_then(|m| m.liquidation_distance);
+            (p, distance)
+        })
+        .collect();
+    at_risk.sort_by(|(a, da), (b, db)| {
+        let distance = |d: &Option<f64>| d.unwrap_or(f64::INFINITY);
+        distance(da)
+            .total_cmp(&distance(db))
+            .then(b.notional().abs().total_cmp(&a.notional().abs()))
+            .then(a.pnl.total_cmp(&b.pnl))
+    });
+
+    for (position, liquidation_distance) in at_risk {
+        let spec = instruments.iter().find(|i| i.symbol == position.symbol);
+        let held = to_decimal(position.quantity.abs())?;
+        let rounded = match spec {
+            Some(spec) => round_down(held, spec.lot_size)?,
+            None => held,
+        };
+        let quantity = to_f64(rounded);
+        let min_quantity = spec.and_then(|s| s.min_quantity).unwrap_or(0.0);
+        if quantity <= 0.0 || quantity < min_quantity {
+            plan.skipped.push(SkippedClose {
+                symbol: position.symbol.clone(),
+                quantity: position.quantity,
+                reason: "Below the instrument's minimum order size".to_string(),
+            });
+            continue;
+        }
+
+        let closing_long = position.quantity > 0.0;
+        let mark = position.current_price;
+        // Without a mark there is nothing to price a limit against; don't
+        // silently turn the requested limit into a market order
+        if close_type == CloseType::Limit && !(mark.is_finite() && mark > 0.0) {
+            plan.skipped.push(SkippedClose {
+                symbol: position.symbol.clone(),
+                quantity: position.quantity,
+                reason: "No mark price to set a limit against".to_string(),
+            });
+            continue;
+        }
+        let price = if close_type == CloseType::Limit {
+            let tick = spec.map(|s| s.tick_size).unwrap_or(0.0);
+            let mark = to_decimal(mark)?;
+            let price = if closing_long {
+                round_down(mark * (Decimal::ONE - slippage), tick)?
+            } else {
+                round_up(mark * (Decimal::ONE + slippage), tick)?
+            };
+            Some(to_f64(price))
+        } else {
+            None
+        };
+
+        let sequence = plan.orders.len() as u32 + 1;
+        plan.orders.push(CloseOrder {
+            sequence,
+            client_order_id: format!("flatten-{}-{}", timestamp_ms, sequence),
+            symbol: position.symbol.clone(),
+            side: if closing_long { "SELL" } else { "BUY" }.to_string(),
+            quantity,
+            dust: to_f64(held - rounded),
+            order_type: if price.is_some() { "LIMIT" } else { "MARKET" }.to_string(),
+            price,
+            reduce_only: true,
+            notional: position.notional().abs(),
+            unrealized_pnl: position.pnl,
+            liquidation_distance,
+        });
+    }
+
+    Ok(plan)
+}
+
+// Rounding happens in decimal: in f64, multiplying the step back in
+// (3.0 * 0.1) reintroduces binary noise (0.30000000000000004).
+// Reduce-only quantities round down so they never exceed the position.
+fn round_down(value: Decimal, step: f64) -> Result<Decimal> {
+    round_to_step(value, step, Decimal::floor)
+}
+
+fn round_up(value: Decimal, step: f64) -> Result<Decimal> {
+    round_to_step(value, step, Decimal::ceil)
+}
+
+fn round_to_step(value: Decimal, step: f64, round: fn(&Decimal) -> Decimal) -> Result<Decimal> {
+    if step <= 0.0 {
+        return Ok(value);
+    }
+    let step = to_decimal(step)?;
+    value
+        .checked_div(step)
+        .and_then(|steps| round(&steps).checked_mul(step))
+        .ok_or_else(|| GuardianError::Validation(format!("{} is out of range for a step of {}", value, step)))
+}
+
+// Shortest decimal that round-trips, so 0.1 converts to exactly 0.1
+fn to_decimal(value: f64) -> Result<Decimal> {
+    Decimal::from_f64(value)
+        .ok_or_else(|| GuardianError::Validation(format!("{} cannot be represented as a decimal", value)))
+}
+
+fn to_f64(value: Decimal) -> f64 {
+    // Every Decimal is within f64 range
+    value.to_f64().unwrap_or(f64::NAN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{Liquidity, Side};
+
+    fn position(symbol: &str, side: Side, quantity: f64, entry: f64, mark: f64) -> Position {
+        let mut p = Position {
+            symbol: symbol.to_string(),
+            ..Position::default()
+        };
+        p.apply_fill(side, quantity, entry, 0.0, Liquidity::Taker);
+        p.current_price = mark;
+        p.pnl = p.unrealized_pnl();
+        p
+    }
+
+    fn spec(symbol: &str, tick_size: f64, lot_size: f64) -> InstrumentSpec {
+        InstrumentSpec {
+            symbol: symbol.to_string(),
+            tick_size,
+            lot_size,
+            min_quantity: Some(lot_size),
+        }
+    }
+
+    #[test]
+    fn test_closest_to_liquidation_closes_first() {
+        let positions = [
+            position("BTC", Side::Buy, 0.0347, 50_000.0, 50_000.0),
+            position("ETH", Side::Sell, 2.0, 3_000.0, 3_100.0),
+            position("DOGE", Side::Buy, 0.4, 0.1, 0.1),
+        ];
+        let margins = [
+            PositionMargin {
+                symbol: "BTC".to_string(),
+                liquidation_distance: Some(0.20),
+                ..PositionMargin::default()
+            },
+            PositionMargin {
+                symbol: "ETH".to_string(),
+                liquidation_distance: Some(0.05),
+                ..PositionMargin::default()
+            },
+        ];
+        let instruments = [spec("BTC", 0.5, 0.001), spec("ETH", 0.01, 0.01), spec("DOGE", 0.0001, 1.0)];
+        let options = FlattenOptions {
+            order_type: Some("limit".to_string()),
+            slippage_bps: Some(100.0),
+            ..FlattenOptions::default()
+        };
+
+        let plan = plan(&positions, &margins, &[], &instruments, &options, 1_000).unwrap();
+
+        assert_eq!(plan.orders.len(), 2);
+        let eth = &plan.orders[0];
+        assert_eq!((eth.symbol.as_str(), eth.side.as_str(), eth.sequence), ("ETH", "BUY", 1));
+        assert_eq!(eth.price, Some(3_131.0));
+        assert_eq!(eth.dust, 0.0);
+        let btc = &plan.orders[1];
+        assert_eq!(btc.quantity, 0.034);
+        assert_eq!(btc.dust, 0.0007);
+        assert_eq!(btc.price, Some(49_500.0));
+        assert!(btc.reduce_only);
+        assert_eq!(plan.skipped[0].symbol, "DOGE");
+    }
+
+    #[test]
+    fn test_limit_close_without_a_mark_is_skipped() {
+        let positions = [
+            position("BTC", Side::Buy, 1.0, 50_000.0, 0.0),
+            position("ETH", Side::Sell, 2.0, 3_000.0, 3_000.0),
+        ];
+        let limit = FlattenOptions {
+            order_type: Some("LIMIT".to_string()),
+            ..FlattenOptions::default()
+        };
+
+        let limited = plan(&positions, &[], &[], &[], &limit, 1_000).unwrap();
+        assert_eq!(limited.orders.len(), 1);
+        assert_eq!((limited.orders[0].symbol.as_str(), limited.orders[0].order_type.as_str()), ("ETH", "LIMIT"));
+        assert_eq!(limited.skipped.len(), 1);
+        assert_eq!(limited.skipped[0].symbol, "BTC");
+        assert_eq!(limited.skipped[0].reason, "No mark price to set a limit against");
+
+        // A market close needs no mark
+        let market = plan(&positions, &[], &[], &[], &FlattenOptions::default(), 1_000).unwrap();
+        assert_eq!(market.orders.len(), 2);
+        assert!(market.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_rounding_to_step_is_exact() {
+        let round = |value: f64, step: f64| to_f64(round_down(to_decimal(value).unwrap(), step).unwrap());
+        assert_eq!(round(0.3, 0.1), 0.3);
+        assert_eq!(round(2.3, 0.1), 2.3);
+        assert_eq!(round(2.39, 0.1), 2.3);
+        assert_eq!(round(0.0347, 0.001), 0.034);
+        assert_eq!(to_f64(round_up(to_decimal(3_100.001).unwrap(), 0.01).unwrap()), 3_100.01);
+        assert_eq!(to_f64(round_up(to_decimal(0.7).unwrap(), 0.1).unwrap()), 0.7);
+    }
+}
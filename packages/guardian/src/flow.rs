@@ -0,0 +1,287 @@
+use crate::error::{GuardianError, Result};
+use napi_derive::napi;
+use std::collections::{HashMap, VecDeque};
+
+/// Volume per VPIN bucket until `configure_flow` is called for the symbol
+pub const DEFAULT_BUCKET_VOLUME: f64 = 100.0;
+/// Number of completed buckets VPIN is averaged over
+pub const DEFAULT_WINDOW_BUCKETS: usize = 50;
+/// Largest VPIN window `configure_flow` accepts
+pub const MAX_WINDOW_BUCKETS: usize = 10_000;
+/// Completed buckets required before VPIN is reported as a risk fact
+pub const MIN_BUCKETS_FOR_VPIN: usize = 10;
+
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct TradePrint {
+    pub price: f64,
+    pub size: f64,
+    /// Aggressor side ("BUY"/"SELL"). Inferred with the tick rule when absent.
+    pub side: Option<String>,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct FlowMetrics {
+    /// Volume-synchronised probability of informed trading (0.0 to 1.0)
+    pub vpin: f64,
+    /// Buy minus sell aggressor volume over the VPIN window
+    pub signed_flow: f64,
+    /// Signed flow normalised by window volume (-1.0 to 1.0)
+    pub flow_imbalance: f64,
+    /// Buy minus sell aggressor volume since the first print
+    pub cumulative_signed_flow: f64,
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+    pub completed_buckets: u32,
+    pub trade_count: u32,
+    pub last_price: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Bucket {
+    buy: f64,
+    sell: f64,
+}
+
+impl Bucket {
+    fn volume(&self) -> f64 {
+        self.buy + self.sell
+    }
+}
+
+#[derive(Debug)]
+struct SymbolFlow {
+    bucket_volume: f64,
+    window: usize,
+    buckets: VecDeque<Bucket>,
+    current: Bucket,
+    last_price: Option<f64>,
+    // +1.0 buy / -1.0 sell, carried through zero ticks
+    last_direction: f64,
+    cumulative_signed_flow: f64,
+    trade_count: u32,
+}
+
+impl SymbolFlow {
+    fn new(bucket_volume: f64, window: usize) -> Self {
+        SymbolFlow {
+            bucket_volume,
+            window,
+            buckets: VecDeque::with_capacity(window),
+            current: Bucket::default(),
+            last_price: None,
+            last_direction: 0.0,
+            cumulative_signed_flow: 0.0,
+            trade_count: 0,
+        }
+    }
+
+    // `aggressor` is +1.0 buy / -1.0 sell, None to infer with the tick rule
+    fn ingest(&mut self, price: f64, size: f64, aggressor: Option<f64>) {
+        let direction = aggressor.unwrap_or_else(|| self.tick_rule(price));
+        if direction != 0.0 {
+            self.last_direction = direction;
+        }
+        self.last_price = Some(price);
+        self.trade_count += 1;
+        self.cumulative_signed_flow += direction * size;
+
+        // Unclassifiable prints (first print on a zero tick) split evenly
+        let (mut buy, mut sell) = match direction {
+            d if d > 0.0 => (size, 0.0),
+            d if d < 0.0 => (0.0, size),
+            _ => (size / 2.0, size / 2.0),
+        };
+
+        // Fill buckets, spilling large prints across bucket boundaries
+        while buy + sell > 0.0 {
+            let room = self.bucket_volume - self.current.volume();
+            let take = (buy + sell).min(room);
+            let buy_share = take * buy / (buy + sell);
+            let sell_share = take - buy_share;
+
+            self.current.buy += buy_share;
+            self.current.sell += sell_share;
+            buy -= buy_share;
+            sell -= sell_share;
+
+            if self.current.volume() >= self.bucket_volume - f64::EPSILON {
+                self.buckets.push_back(std::mem::take(&mut self.current));
+                if self.buckets.len() > self.window {
+                    self.buckets.pop_front();
+                }
+            }
+            if buy + sell <= f64::EPSILON {
+                break;
+            }
+        }
+    }
+
+    fn tick_rule(&self, price: f64) -> f64 {
+        match self.last_price {
+            Some(last) if price > last => 1.0,
+            Some(last) if price < last => -1.0,
+            _ => self.last_direction,
+        }
+    }
+
+    fn metrics(&self) -> FlowMetrics {
+        let buy_volume: f64 = self.buckets.iter().map(|b| b.buy).sum::<f64>() + self.current.buy;
+        let sell_volume: f64 = self.buckets.iter().map(|b| b.sell).sum::<f64>() + self.current.sell;
+        let signed_flow = buy_volume - sell_volume;
+        let total = buy_volume + sell_volume;
+
+        let vpin = if self.buckets.is_empty() {
+            0.0
+        } else {
+            let imbalance: f64 = self.buckets.iter().map(|b| (b.buy - b.sell).abs()).sum();
+            imbalance / (self.buckets.len() as f64 * self.bucket_volume)
+        };
+
+        FlowMetrics {
+            vpin,
+            signed_flow,
+            flow_imbalance: if total > 0.0 { signed_flow / total } else { 0.0 },
+            cumulative_signed_flow: self.cumulative_signed_flow,
+            buy_volume,
+            sell_volume,
+            completed_buckets: self.buckets.len() as u32,
+            trade_count: self.trade_count,
+            last_price: self.last_price.unwrap_or(0.0),
+        }
+    }
+}
+
+/// Per-symbol aggressive trade flow and VPIN toxicity.
+#[derive(Debug, Default)]
+pub struct FlowTracker {
+    symbols: HashMap<String, SymbolFlow>,
+}
+
+impl FlowTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reset the symbol's buckets with a new bucket volume and window.
+    pub fn configure(&mut self, symbol: &str, bucket_volume: f64, window: usize) {
+        let bucket_volume = if bucket_volume > 0.0 { bucket_volume } else { DEFAULT_BUCKET_VOLUME };
+        self.symbols
+            .insert(symbol.to_string(), SymbolFlow::new(bucket_volume, window.max(1)));
+    }
+
+    pub fn ingest(&mut self, symbol: &str, price: f64, size: f64, side: Option<&str>) -> Result<()> {
+        let aggressor = validate_print(price, size, side)?;
+        self.symbol_flow(symbol).ingest(price, size, aggressor);
+        Ok(())
+    }
+
+    /// Ingest prints in order. Nothing is ingested if any print is invalid.
+    pub fn ingest_all(&mut self, symbol: &str, trades: &[TradePrint]) -> Result<()> {
+        let aggressors = trades
+            .iter()
+            .map(|t| validate_print(t.price, t.size, t.side.as_deref()))
+            .collect::<Result<Vec<_>>>()?;
+        let flow = self.symbol_flow(symbol);
+        for (trade, aggressor) in trades.iter().zip(aggressors) {
+            flow.ingest(trade.price, trade.size, aggressor);
+        }
+        Ok(())
+    }
+
+    fn symbol_flow(&mut self, symbol: &str) -> &mut SymbolFlow {
+        self.symbols
+            .entry(symbol.to_string())
+            .or_insert_with(|| SymbolFlow::new(DEFAULT_BUCKET_VOLUME, DEFAULT_WINDOW_BUCKETS))
+    }
+
+    pub fn metrics(&self, symbol: &str) -> FlowMetrics {
+        self.symbols
+            .get(symbol)
+            .map(SymbolFlow::metrics)
+            .unwrap_or_default()
+    }
+}
+
+/// Aggressor direction of a valid print: +1.0 buy, -1.0 sell, None to infer.
+fn validate_print(price: f64, size: f64, side: Option<&str>) -> Result<Option<f64>> {
+    if !(price.is_finite() && price > 0.0) {
+        return Err(GuardianError::Validation(format!("Trade price must be positive, got {}", price)));
+    }
+    if !(size.is_finite() && size > 0.0) {
+        return Err(GuardianError::Validation(format!("Trade size must be positive, got {}", size)));
+    }
+    match side.map(str::to_ascii_uppercase).as_deref() {
+        None => Ok(None),
+        Some("BUY") => Ok(Some(1.0)),
+        Some("SELL") => Ok(Some(-1.0)),
+        Some(other) => Err(GuardianError::Validation(format!("Invalid trade side: {}", other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_sided_flow_is_fully_toxic() {
+        let mut tracker = FlowTracker::new();
+        tracker.configure("BTC", 10.0, 5);
+
+        for i in 0..50 {
+            tracker.ingest("BTC", 100.0 + i as f64, 2.0, None).unwrap();
+        }
+
+        let m = tracker.metrics("BTC");
+        assert_eq!(m.completed_buckets, 5);
+        assert!((m.vpin - 1.0).abs() < 1e-9);
+        // First print has no prior tick and is split evenly
+        assert_eq!(m.cumulative_signed_flow, 98.0);
+    }
+
+    #[test]
+    fn test_balanced_flow_has_low_vpin() {
+        let mut tracker = FlowTracker::new();
+        tracker.configure("BTC", 10.0, 5);
+
+        for _ in 0..25 {
+            tracker.ingest("BTC", 100.0, 1.0, Some("BUY")).unwrap();
+            tracker.ingest("BTC", 100.0, 1.0, Some("sell")).unwrap();
+        }
+
+        let m = tracker.metrics("BTC");
+        assert!(m.vpin < 1e-9);
+        assert!(m.flow_imbalance.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_large_print_spills_across_buckets() {
+        let mut tracker = FlowTracker::new();
+        tracker.configure("BTC", 10.0, 5);
+
+        tracker.ingest("BTC", 100.0, 25.0, Some("SELL")).unwrap();
+
+        let m = tracker.metrics("BTC");
+        assert_eq!(m.completed_buckets, 2);
+        assert_eq!(m.sell_volume, 25.0);
+    }
+
+    #[test]
+    fn test_invalid_prints_are_rejected() {
+        let mut tracker = FlowTracker::new();
+        for (price, size, side) in [(0.0, 1.0, None), (100.0, f64::NAN, None), (100.0, 1.0, Some("HOLD"))] {
+            let error = tracker.ingest("BTC", price, size, side).unwrap_err();
+            assert_eq!(error.kind(), "VALIDATION");
+        }
+
+        // A bad print anywhere in a batch ingests none of it
+        let print = |price: f64| TradePrint {
+            price,
+            size: 1.0,
+            side: None,
+        };
+        assert!(tracker.ingest_all("BTC", &[print(100.0), print(-1.0)]).is_err());
+        assert_eq!(tracker.metrics("BTC").trade_count, 0);
+    }
+}
@@ -1,53 +1,117 @@
 #![deny(clippy::all)]
 
+use error::GuardianError;
+use napi::bindgen_prelude::Buffer;
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::{Env, JsFunction};
 use napi_derive::napi;
-// use napi::bindgen_prelude::*; unused
+use std::sync::Mutex;
 
+mod analytics;
+mod attribution;
+mod error;
+mod events;
+mod flatten;
+mod flow;
+mod margin;
+mod microstructure;
+mod orders;
+mod reconcile;
 mod rules;
 mod state;
+mod walls;
 
 #[napi]
 pub struct TitanGuardian {
     // Internal state optimized for time-series (Polars DataFrame could go here if persistent)
     state: state::PortfolioState,
+    // Large resting levels tracked across snapshots (spoofing detection)
+    walls: Mutex<walls::WallTracker>,
+    // Aggressive trade prints and VPIN buckets per symbol
+    flow: Mutex<flow::FlowTracker>,
+}
+
+impl Default for TitanGuardian {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[napi]
 impl TitanGuardian {
     #[napi(constructor)]
     pub fn new() -> Self {
-        TitanGuardian {
-            state: state::PortfolioState::new(),
-        }
+        Self::with_state(state::PortfolioState::new())
+    }
+
+    /// Restore the portfolio from a snapshot file (created on first checkpoint)
+    /// and checkpoint to it every `checkpoint_interval_ms` while it has unsaved changes
+    /// (default 5000, 0 = on-demand only).
+    /// Throws if the existing snapshot is unreadable.
+    #[napi(factory)]
+    pub fn open(snapshot_path: String, checkpoint_interval_ms: Option<u32>) -> napi::Result<Self> {
+        let interval = checkpoint_interval_ms.unwrap_or(state::DEFAULT_CHECKPOINT_INTERVAL_MS);
+        let portfolio = state::PortfolioState::open(snapshot_path, interval)?;
+        Ok(Self::with_state(portfolio))
+    }
+
+    /// Write the portfolio snapshot to disk now.
+    #[napi]
+    pub fn checkpoint(&self) -> napi::Result<()> {
+        Ok(self.state.checkpoint()?)
     }
 
     /// Calculate Order Flow Imbalance (OFI) from raw Orderbook snapshot.
     /// Uses Zero-Copy Buffer for maximum performance.
     /// Input: JSON buffer of bids/asks, but parsed manually for speed.
     /// For this version, we accept strings for simplicity, but Buffer is supported.
+    /// Throws on malformed JSON, non-positive prices and negative sizes;
+    /// returns 0.0 when both sides are empty.
     #[napi]
-    pub fn calculate_ofi(&self, bids_json: String, asks_json: String) -> f64 {
+    pub fn calculate_ofi(&self, bids_json: String, asks_json: String) -> napi::Result<f64> {
         // In a real optimized scenario, we'd parse bytes directly with `simd-json`
         // Here we use standard serde for safety/simplicity in this iteration
-        let bids: Vec<(f64, f64)> = serde_json::from_str(&bids_json).unwrap_or_default();
-        let asks: Vec<(f64, f64)> = serde_json::from_str(&asks_json).unwrap_or_default();
+        let bids = microstructure::parse_levels(&bids_json)?;
+        let asks = microstructure::parse_levels(&asks_json)?;
 
         let bid_depth: f64 = bids.iter().take(5).map(|(_p, v)| v).sum();
         let ask_depth: f64 = asks.iter().take(5).map(|(_p, v)| v).sum();
         let total = bid_depth + ask_depth;
 
         if total == 0.0 {
-            0.0
+            Ok(0.0)
         } else {
             // Normalized OFI (-1.0 to 1.0)
-            (bid_depth - ask_depth) / total
+            Ok((bid_depth - ask_depth) / total)
         }
     }
 
+    /// Microstructure features (microprice, queue imbalance, weighted mid,
+    /// decayed depth pressure, book slope) for a single Orderbook snapshot.
+    /// `level_decay` defaults to 0.5 per level.
+    #[napi]
+    pub fn compute_book_features(
+        &self,
+        bids_json: String,
+        asks_json: String,
+        level_decay: Option<f64>,
+    ) -> napi::Result<microstructure::BookFeatures> {
+        let level_decay = level_decay.unwrap_or(microstructure::DEFAULT_LEVEL_DECAY);
+        microstructure::validate_level_decay(level_decay)?;
+        let bids = microstructure::parse_levels(&bids_json)?;
+        let asks = microstructure::parse_levels(&asks_json)?;
+        Ok(microstructure::compute_features(
+            &bids,
+            &asks,
+            level_decay,
+        ))
+    }
+
     /// The "Silicon Guardian" Validation Gate
     /// Validates AI intent against Datalog risk invariants.
     /// Returns: { allowed: boolean, reason: string }
     #[napi]
+    #[allow(clippy::too_many_arguments)]
     pub fn validate_intent(
         &self, 
         side: String, 
@@ -58,25 +122,513 @@ impl TitanGuardian {
         adx: f64,
         rsi: f64,
         position_count: i64
-    ) -> String {
+    ) -> napi::Result<String> {
         // Enforce invariants
-        match rules::check_risk(&side, size, vol, ofi, &trend, adx, rsi, position_count) {
-            Some(reason) => {
-                format!("{{\"allowed\": false, \"reason\": \"{}\"}}", reason)
-            },
-            None => {
-                "{\"allowed\": true, \"reason\": \"Approved by Silicon Guardian\"}".to_string()
-            }
+        Ok(verdict_json(rules::check_risk(&side, size, vol, ofi, &trend, adx, rsi, position_count)))
+    }
+
+    /// Track walls across snapshots for `symbol` and score how likely they are spoofed.
+    /// Walls pulled as price approaches them raise the side's spoof score.
+    /// Levels are matched on `tick_size` (default 1e-8); scores fade out
+    /// when snapshots stop arriving.
+    #[napi]
+    pub fn track_walls(
+        &self,
+        symbol: String,
+        bids_json: String,
+        asks_json: String,
+        timestamp_ms: i64,
+        tick_size: Option<f64>,
+    ) -> napi::Result<walls::WallReport> {
+        let tick_size = tick_size.unwrap_or(walls::DEFAULT_TICK_SIZE);
+        if !(tick_size.is_finite() && tick_size > 0.0) {
+            return Err(GuardianError::Validation("Tick size must be positive".to_string()).into());
+        }
+        let bids = microstructure::parse_levels(&bids_json)?;
+        let asks = microstructure::parse_levels(&asks_json)?;
+        let mut tracker = error::lock(&self.walls, "Wall tracker")?;
+        Ok(tracker.update(&symbol, &bids, &asks, timestamp_ms, tick_size))
+    }
+
+    /// Configure VPIN volume buckets for `symbol` (resets its flow history).
+    /// `window_buckets` defaults to 50.
+    #[napi]
+    pub fn configure_flow(&self, symbol: String, bucket_volume: f64, window_buckets: Option<u32>) -> napi::Result<()> {
+        if !(bucket_volume.is_finite() && bucket_volume > 0.0) {
+            return Err(GuardianError::Validation("Bucket volume must be positive".to_string()).into());
+        }
+        let window = window_buckets.map(|w| w as usize).unwrap_or(flow::DEFAULT_WINDOW_BUCKETS);
+        if !(1..=flow::MAX_WINDOW_BUCKETS).contains(&window) {
+            return Err(GuardianError::Validation(format!(
+                "Window must be between 1 and {} buckets",
+                flow::MAX_WINDOW_BUCKETS
+            ))
+            .into());
         }
+        error::lock(&self.flow, "Flow tracker")?.configure(&symbol, bucket_volume, window);
+        Ok(())
+    }
+
+    /// Ingest one trade print. `side` is the aggressor ("BUY"/"SELL");
+    /// when omitted it is inferred with the tick rule.
+    /// Throws on a non-positive price or size or an unknown side.
+    #[napi]
+    pub fn ingest_trade(&self, symbol: String, price: f64, size: f64, side: Option<String>) -> napi::Result<()> {
+        Ok(error::lock(&self.flow, "Flow tracker")?.ingest(&symbol, price, size, side.as_deref())?)
+    }
+
+    /// Ingest a batch of trade prints in arrival order.
+    /// Throws, ingesting none of them, if any print is invalid.
+    #[napi]
+    pub fn ingest_trades(&self, symbol: String, trades: Vec<flow::TradePrint>) -> napi::Result<()> {
+        Ok(error::lock(&self.flow, "Flow tracker")?.ingest_all(&symbol, &trades)?)
+    }
+
+    /// VPIN toxicity and signed trade flow for `symbol`.
+    #[napi]
+    pub fn get_flow_metrics(&self, symbol: String) -> napi::Result<flow::FlowMetrics> {
+        Ok(error::lock(&self.flow, "Flow tracker")?.metrics(&symbol))
+    }
+
+    /// Same gate as `validate_intent`, enriched with facts the guardian
+    /// tracks for `symbol` (wall spoof scores, VPIN toxicity, margin usage and
+    /// liquidation distance after the trade, open orders and resting notional).
+    /// Trades that only shrink the position are exempt from the toxicity and
+    /// order-exposure blocks.
+    #[napi]
+    #[allow(clippy::too_many_arguments)]
+    pub fn validate_intent_for_symbol(
+        &self,
+        symbol: String,
+        side: String,
+        size: f64,
+        vol: f64,
+        ofi: f64,
+        trend: String,
+        adx: f64,
+        rsi: f64,
+        position_count: i64,
+    ) -> napi::Result<String> {
+        let facts = self.market_facts(&symbol, &side, size)?;
+        Ok(verdict_json(rules::check_risk_with_facts(
+            &side, size, vol, ofi, &trend, adx, rsi, position_count, &facts,
+        )))
+    }
+
+    #[napi]
+    pub fn update_position(&self, symbol: String, quantity: f64, price: f64) -> napi::Result<()> {
+        self.state.update_position(symbol, quantity, price)?;
+        Ok(())
+    }
+
+    /// Book an execution (`side` "BUY"/"SELL") against the symbol's position:
+    /// averages entries, realises PnL on partial/full closes and handles flips.
+    /// The fill is appended to the ledger; an already-booked `fill_id` is ignored.
+    /// `liquidity` is "MAKER" or "TAKER" (default) for fee attribution;
+    /// `strategy_id` and `signal_source` ("MathGuardian", "NeuralCortex",
+    /// "SymbolicConsensus") tag the fill for PnL attribution.
+    #[napi]
+    #[allow(clippy::too_many_arguments)]
+    pub fn apply_fill(
+        &self,
+        symbol: String,
+        side: String,
+        quantity: f64,
+        price: f64,
+        fee: Option<f64>,
+        signal_hash: Option<String>,
+        fill_id: Option<String>,
+        liquidity: Option<String>,
+        strategy_id: Option<String>,
+        signal_source: Option<String>,
+    ) -> napi::Result<state::Position> {
+        let side = state::Side::parse(&side)
+            .ok_or_else(|| GuardianError::Validation(format!("Invalid side: {}", side)))?;
+        let liquidity = parse_liquidity(liquidity)?;
+        let signal_source = match signal_source {
+            Some(s) => Some(
+                state::SignalSource::parse(&s)
+                    .ok_or_else(|| GuardianError::Validation(format!("Invalid signal source: {}", s)))?,
+            ),
+            None => None,
+        };
+        validate_fill(quantity, price, fee)?;
+        Ok(self.state.record_fill(state::FillInput {
+            id: fill_id,
+            signal_hash,
+            liquidity,
+            strategy_id,
+            signal_source,
+            ..state::FillInput::new(&symbol, side, quantity, price, fee.unwrap_or(0.0))
+        })?)
+    }
+
+    /// Start tracking an order (state NEW) before sending it to the exchange.
+    #[napi]
+    pub fn submit_order(&self, order: orders::OrderInput) -> napi::Result<orders::Order> {
+        Ok(self.state.submit_order(&order)?)
+    }
+
+    /// The exchange accepted the order. A late ack after fills is a no-op.
+    #[napi]
+    pub fn acknowledge_order(&self, id: String) -> napi::Result<orders::Order> {
+        self.transition_order(&id, orders::OrderStatus::Acknowledged, None)
+    }
+
+    #[napi]
+    pub fn cancel_order(&self, id: String, reason: Option<String>) -> napi::Result<orders::Order> {
+        self.transition_order(&id, orders::OrderStatus::Cancelled, reason)
+    }
+
+    /// The exchange refused the order (only before it was acknowledged).
+    #[napi]
+    pub fn reject_order(&self, id: String, reason: Option<String>) -> napi::Result<orders::Order> {
+        self.transition_order(&id, orders::OrderStatus::Rejected, reason)
+    }
+
+    /// Book an execution against a tracked order. Updates the fill ledger and
+    /// position, and moves the order to PARTIALLY_FILLED or FILLED. Executions
+    /// that raced our cancel are still booked (a partial one leaves it CANCELLED).
+    /// Throws on overfills and on FILLED or REJECTED orders.
+    #[napi]
+    pub fn fill_order(
+        &self,
+        id: String,
+        quantity: f64,
+        price: f64,
+        fee: Option<f64>,
+        liquidity: Option<String>,
+        fill_id: Option<String>,
+    ) -> napi::Result<orders::Order> {
+        let liquidity = parse_liquidity(liquidity)?;
+        validate_fill(quantity, price, fee)?;
+        Ok(self
+            .state
+            .fill_order(&id, quantity, price, fee.unwrap_or(0.0), liquidity, fill_id)?)
+    }
+
+    #[napi]
+    pub fn get_order(&self, id: String) -> napi::Result<Option<orders::Order>> {
+        Ok(self.state.order(&id)?)
+    }
+
+    /// Tracked orders, oldest first. `open_only` keeps NEW, ACKNOWLEDGED and
+    /// PARTIALLY_FILLED orders.
+    #[napi]
+    pub fn get_orders(&self, symbol: Option<String>, open_only: Option<bool>) -> napi::Result<Vec<orders::Order>> {
+        Ok(self.state.orders(symbol.as_deref(), open_only.unwrap_or(false))?)
+    }
+
+    /// Ledger fills matching the optional filter, in booking order.
+    #[napi]
+    pub fn get_fills(&self, filter: Option<state::FillFilter>) -> napi::Result<Vec<state::Fill>> {
+        Ok(self.state.fills(&filter.unwrap_or_default())?)
+    }
+
+    /// Rebuild positions from the fill ledger and report any symbol whose
+    /// stored position disagrees.
+    #[napi]
+    pub fn rebuild_positions_from_ledger(&self) -> napi::Result<state::LedgerCheck> {
+        Ok(self.state.rebuild_from_ledger()?)
+    }
+
+    #[napi]
+    pub fn get_position(&self, symbol: String) -> napi::Result<Option<state::Position>> {
+        Ok(self.state.position(&symbol)?)
+    }
+
+    /// Book a perpetual funding payment for `symbol`. `amount` (positive =
+    /// received) defaults to `-rate * notional` at the current mark.
+    #[napi]
+    pub fn apply_funding(&self, symbol: String, rate: f64, amount: Option<f64>) -> napi::Result<state::FundingPayment> {
+        Ok(self.state.apply_funding(&symbol, rate, amount)?)
+    }
+
+    /// Funding payments booked for `symbol` (all symbols if omitted).
+    #[napi]
+    pub fn get_funding_payments(&self, symbol: Option<String>) -> napi::Result<Vec<state::FundingPayment>> {
+        Ok(self.state.funding_payments(symbol.as_deref())?)
+    }
+
+    /// Gross PnL, maker/taker fees, funding and net PnL per symbol and in total.
+    #[napi]
+    pub fn get_pnl_breakdown(&self) -> napi::Result<state::PnlReport> {
+        Ok(self.state.pnl_breakdown()?)
+    }
+
+    /// Set wallet balance, maintenance margin rate and margin risk limits.
+    /// Omitted fields keep their current value. Returns the resulting settings.
+    #[napi]
+    pub fn configure_account(&self, config: margin::AccountConfig) -> napi::Result<margin::AccountSettings> {
+        Ok(self.state.configure_account(&config)?)
     }
 
+    /// Leverage used for `symbol`'s margin and liquidation estimates (default 1x).
     #[napi]
-    pub fn update_position(&self, symbol: String, quantity: f64, price: f64) {
-        self.state.update_position(symbol, quantity, price);
+    pub fn set_leverage(&self, symbol: String, leverage: f64) -> napi::Result<()> {
+        Ok(self.state.set_leverage(&symbol, leverage)?)
     }
 
+    /// Equity, used and free margin, margin usage and estimated liquidation
+    /// prices at current marks.
     #[napi]
-    pub fn get_portfolio_state(&self) -> String {
-        self.state.get_state_json()
+    pub fn get_account_state(&self) -> napi::Result<margin::AccountState> {
+        Ok(self.state.account_state()?)
+    }
+
+    /// Realised/unrealised PnL per strategy, signal source and symbol,
+    /// replaying each strategy's fills as a separate book.
+    #[napi]
+    pub fn get_pnl_attribution(&self) -> napi::Result<attribution::AttributionReport> {
+        Ok(self.state.attribution()?)
+    }
+
+    /// Recorded equity curve (wallet balance + settled + unrealised PnL), oldest first.
+    #[napi]
+    pub fn get_equity_curve(&self) -> napi::Result<Vec<analytics::EquityPoint>> {
+        Ok(self.state.equity_curve()?)
+    }
+
+    /// Returns, high-water mark, drawdowns, Sharpe/Sortino and closed-trade
+    /// statistics (win rate, profit factor, average duration) over the window.
+    #[napi]
+    pub fn get_performance_analytics(
+        &self,
+        options: Option<analytics::AnalyticsOptions>,
+    ) -> napi::Result<analytics::PerformanceReport> {
+        let options = options.unwrap_or_default();
+        let trades = analytics::closed_trades(&self.state.fills(&state::FillFilter::default())?);
+        Ok(analytics::performance(&self.state.equity_curve()?, &trades, &options)?)
+    }
+
+    /// Emergency flatten: reduce-only close orders for every open position,
+    /// rounded to each instrument's lot/tick size and ordered by risk
+    /// (closest to liquidation first), plus tracked open orders to cancel.
+    #[napi]
+    pub fn plan_flatten_all(
+        &self,
+        instruments: Option<Vec<flatten::InstrumentSpec>>,
+        options: Option<flatten::FlattenOptions>,
+    ) -> napi::Result<flatten::FlattenPlan> {
+        let account = self.state.account_state()?;
+        Ok(flatten::plan(
+            &self.state.positions()?,
+            &account.positions,
+            &self.state.orders(None, true)?,
+            &instruments.unwrap_or_default(),
+            &options.unwrap_or_default(),
+            state::now_ms(),
+        )?)
+    }
+
+    /// Compare guardian positions against the exchange's position list.
+    /// Reports missing/extra symbols and quantity or entry-price drift beyond
+    /// tolerance; with `adoptExchange` the exchange values replace ours and the
+    /// adjustment is booked in the fill ledger.
+    #[napi]
+    pub fn reconcile_positions(
+        &self,
+        exchange_positions: Vec<reconcile::ExchangePosition>,
+        options: Option<reconcile::ReconcileOptions>,
+    ) -> napi::Result<reconcile::ReconcileReport> {
+        Ok(reconcile::reconcile(
+            &self.state,
+            &exchange_positions,
+            &options.unwrap_or_default(),
+            state::now_ms(),
+        )?)
+    }
+
+    /// Mark a position to market. Returns false if no position is held in `symbol`.
+    #[napi]
+    pub fn update_price(&self, symbol: String, price: f64) -> napi::Result<bool> {
+        Ok(self.state.update_price(&symbol, price)?.is_some())
+    }
+
+    /// Mark several positions to market in one commit.
+    /// Returns how many held positions were revalued.
+    #[napi]
+    pub fn update_prices(&self, updates: Vec<state::PriceUpdate>) -> napi::Result<u32> {
+        Ok(self.state.update_prices(&updates)?)
+    }
+
+    /// Exposure and unrealised PnL across all positions.
+    #[napi]
+    pub fn get_portfolio_totals(&self) -> napi::Result<state::PortfolioTotals> {
+        Ok(self.state.totals()?)
+    }
+
+    /// Encoded version vector of this replica, to send to peers for `exportUpdates`.
+    #[napi]
+    pub fn get_version(&self) -> napi::Result<Buffer> {
+        Ok(self.state.version()?.into())
+    }
+
+    /// Portfolio updates a peer at version `since` is missing (all history if omitted).
+    #[napi]
+    pub fn export_updates(&self, since: Option<Buffer>) -> napi::Result<Buffer> {
+        Ok(self.state.export_updates(since.as_deref())?.into())
+    }
+
+    /// Merge updates from another replica (bot process or monitoring UI).
+    /// Returns false if some updates are waiting on missing history.
+    #[napi]
+    pub fn import_updates(&self, updates: Buffer) -> napi::Result<bool> {
+        Ok(self.state.import_updates(&updates)?)
+    }
+
+    /// Hex-encoded version of the current portfolio. Log it with trade approvals
+    /// to replay what the guardian believed via `get_portfolio_at_version`.
+    #[napi]
+    pub fn get_current_version(&self) -> napi::Result<String> {
+        Ok(self.state.current_version()?)
+    }
+
+    /// Recorded portfolio commits (timestamp + version), oldest first. Price marks are not recorded.
+    #[napi]
+    pub fn get_commit_history(&self) -> napi::Result<Vec<state::CommitRecord>> {
+        Ok(self.state.commit_history()?)
+    }
+
+    /// Positions as they were at a recorded version.
+    #[napi]
+    pub fn get_portfolio_at_version(&self, version: String) -> napi::Result<Vec<state::Position>> {
+        Ok(self.state.positions_at_version(&version)?)
+    }
+
+    /// Positions as the guardian believed them at `timestamp_ms` (unix ms).
+    #[napi]
+    pub fn get_portfolio_at_timestamp(&self, timestamp_ms: i64) -> napi::Result<Vec<state::Position>> {
+        Ok(self.state.positions_at_timestamp(timestamp_ms)?)
+    }
+
+    /// Call `callback` with the changed positions after every commit or replica
+    /// import that touches them (quantity, price, PnL, fees, funding).
+    /// Returns an id for `unsubscribe_portfolio`.
+    #[napi(ts_args_type = "callback: (changes: Array<PositionChange>) => void")]
+    pub fn subscribe_portfolio(&self, env: Env, callback: JsFunction) -> napi::Result<u32> {
+        let mut tsfn: ThreadsafeFunction<Vec<events::PositionChange>, ErrorStrategy::Fatal> =
+            callback.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))?;
+        // A subscription alone should not keep the Node process alive
+        tsfn.unref(&env)?;
+
+        Ok(self.state.subscribe(Box::new(move |changes| {
+            tsfn.call(changes.to_vec(), ThreadsafeFunctionCallMode::NonBlocking);
+        }))?)
+    }
+
+    /// Stop a `subscribe_portfolio` callback. Returns false for unknown ids.
+    #[napi]
+    pub fn unsubscribe_portfolio(&self, id: u32) -> napi::Result<bool> {
+        Ok(self.state.unsubscribe(id)?)
+    }
+
+    /// Positions with aggregate exposure/PnL totals and the last update time.
+    #[napi]
+    pub fn get_portfolio_state(&self) -> napi::Result<state::PortfolioSnapshot> {
+        Ok(self.state.snapshot()?)
+    }
+
+    /// Raw Loro value of the positions map as JSON. Debugging only; the shape
+    /// follows the document layout and may change.
+    #[napi]
+    pub fn dump_portfolio_state(&self) -> napi::Result<String> {
+        Ok(self.state.get_state_json()?)
+    }
+}
+
+impl TitanGuardian {
+    fn with_state(state: state::PortfolioState) -> Self {
+        TitanGuardian {
+            state,
+            walls: Mutex::new(walls::WallTracker::new()),
+            flow: Mutex::new(flow::FlowTracker::new()),
+        }
+    }
+
+    fn transition_order(
+        &self,
+        id: &str,
+        next: orders::OrderStatus,
+        reason: Option<String>,
+    ) -> napi::Result<orders::Order> {
+        Ok(self.state.transition_order(id, next, reason)?)
+    }
+
+    fn market_facts(&self, symbol: &str, side: &str, size: f64) -> error::Result<rules::MarketFacts> {
+        let (bid_wall_spoof, ask_wall_spoof) = error::lock(&self.walls, "Wall tracker")?.spoof_scores(symbol);
+
+        let flow = error::lock(&self.flow, "Flow tracker")?.metrics(symbol);
+        let flow_ready = flow.completed_buckets as usize >= flow::MIN_BUCKETS_FOR_VPIN;
+
+        // Project margin at the position mark, falling back to the last trade print
+        let mark = self
+            .state
+            .position(symbol)?
+            .map(|p| p.current_price)
+            .filter(|price| *price > 0.0)
+            .or((flow.last_price > 0.0).then_some(flow.last_price));
+        let margin = match (state::Side::parse(side), mark) {
+            (Some(side), Some(price)) if size > 0.0 => {
+                let (projection, settings) = self.state.project_margin(symbol, side, size, price)?;
+                Some(rules::MarginFacts {
+                    margin_usage: projection.margin_usage,
+                    projected_margin_usage: projection.projected_margin_usage,
+                    max_margin_usage: settings.max_margin_usage,
+                    liquidation_distance: projection.liquidation_distance,
+                    min_liquidation_distance: settings.min_liquidation_distance,
+                })
+            }
+            _ => None,
+        };
+
+        let orders = self.state.order_exposure(symbol)?;
+        let account = self.state.account_state()?;
+
+        let reduces_position = match (state::Side::parse(side), self.state.position(symbol)?) {
+            (Some(side), Some(position)) => position.is_reduced_by(side, size),
+            _ => false,
+        };
+
+        Ok(rules::MarketFacts {
+            bid_wall_spoof,
+            ask_wall_spoof,
+            vpin: flow_ready.then_some(flow.vpin),
+            flow_imbalance: flow_ready.then_some(flow.flow_imbalance),
+            margin,
+            open_orders: orders.open_orders as i64,
+            resting_notional: orders.resting_notional,
+            equity: (account.wallet_balance != 0.0).then_some(account.equity),
+            reduces_position,
+        })
+    }
+}
+
+fn parse_liquidity(liquidity: Option<String>) -> error::Result<state::Liquidity> {
+    match liquidity {
+        Some(l) => state::Liquidity::parse(&l).ok_or_else(|| GuardianError::Validation(format!("Invalid liquidity: {}", l))),
+        None => Ok(state::Liquidity::default()),
+    }
+}
+
+fn validate_fill(quantity: f64, price: f64, fee: Option<f64>) -> error::Result<()> {
+    let valid = quantity.is_finite() && quantity > 0.0 && price.is_finite() && price > 0.0;
+    if !valid {
+        return Err(GuardianError::Validation("Fill quantity and price must be positive".to_string()));
+    }
+    if !fee.is_none_or(f64::is_finite) {
+        return Err(GuardianError::Validation("Fee must be finite".to_string()));
+    }
+    Ok(())
+}
+
+fn verdict_json(block: Option<String>) -> String {
+    match block {
+        Some(reason) => {
+            format!("{{\"allowed\": false, \"reason\": \"{}\"}}", reason)
+        },
+        None => {
+            "{\"allowed\": true, \"reason\": \"Approved by Silicon Guardian\"}".to_string()
+        }
     }
 }
@@ -0,0 +1,295 @@
+use crate::error::{GuardianError, Result};
+use crate::state::{Liquidity, Position, Side};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+/// Leverage assumed for symbols without an explicit `set_leverage`
+pub const DEFAULT_LEVERAGE: f64 = 1.0;
+/// Maintenance margin as a fraction of notional (0.5%)
+pub const DEFAULT_MAINTENANCE_MARGIN_RATE: f64 = 0.005;
+/// Trades pushing used margin above this fraction of equity are blocked
+pub const DEFAULT_MAX_MARGIN_USAGE: f64 = 0.8;
+/// Trades leaving liquidation closer than this fraction of mark are blocked
+pub const DEFAULT_MIN_LIQUIDATION_DISTANCE: f64 = 0.05;
+
+/// Account-level inputs, stored in the portfolio document.
+#[napi(object)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AccountSettings {
+    /// Collateral as reported by the exchange (realised PnL, fees and funding included)
+    pub wallet_balance: f64,
+    pub maintenance_margin_rate: f64,
+    /// Risk limit: maximum used margin / equity (0.0 to 1.0)
+    pub max_margin_usage: f64,
+    /// Risk limit: minimum |mark - liquidation| / mark
+    pub min_liquidation_distance: f64,
+    /// Realised PnL - fees + funding already booked when `wallet_balance` was
+    /// set; only what is booked after that moves equity
+    pub booked_pnl_at_balance: f64,
+}
+
+impl Default for AccountSettings {
+    fn default() -> Self {
+        AccountSettings {
+            wallet_balance: 0.0,
+            booked_pnl_at_balance: 0.0,
+            maintenance_margin_rate: DEFAULT_MAINTENANCE_MARGIN_RATE,
+            max_margin_usage: DEFAULT_MAX_MARGIN_USAGE,
+            min_liquidation_distance: DEFAULT_MIN_LIQUIDATION_DISTANCE,
+        }
+    }
+}
+
+/// Partial update of `AccountSettings`; omitted fields are left unchanged.
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct AccountConfig {
+    pub wallet_balance: Option<f64>,
+    pub maintenance_margin_rate: Option<f64>,
+    pub max_margin_usage: Option<f64>,
+    pub min_liquidation_distance: Option<f64>,
+}
+
+impl AccountConfig {
+    pub fn validate(&self) -> Result<()> {
+        let fraction = |value: Option<f64>| value.is_none_or(|v| v.is_finite() && (0.0..1.0).contains(&v));
+        if !self.wallet_balance.is_none_or(f64::is_finite) {
+            return Err(GuardianError::Validation("Wallet balance must be finite".to_string()));
+        }
+        if !fraction(self.maintenance_margin_rate) {
+            return Err(GuardianError::Validation("Maintenance margin rate must be in [0, 1)".to_string()));
+        }
+        if !self.max_margin_usage.is_none_or(|v| v.is_finite() && v > 0.0) {
+            return Err(GuardianError::Validation("Max margin usage must be positive".to_string()));
+        }
+        if !fraction(self.min_liquidation_distance) {
+            return Err(GuardianError::Validation("Min liquidation distance must be in [0, 1)".to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, settings: &mut AccountSettings) {
+        if let Some(v) = self.wallet_balance {
+            settings.wallet_balance = v;
+        }
+        if let Some(v) = self.maintenance_margin_rate {
+            settings.maintenance_margin_rate = v;
+        }
+        if let Some(v) = self.max_margin_usage {
+            settings.max_margin_usage = v;
+        }
+        if let Some(v) = self.min_liquidation_distance {
+            settings.min_liquidation_distance = v;
+        }
+    }
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct PositionMargin {
+    pub symbol: String,
+    pub quantity: f64,
+    pub leverage: f64,
+    /// |notional| / leverage at the current mark
+    pub used_margin: f64,
+    /// Estimated (isolated margin) liquidation price, None when flat or unreachable
+    pub liquidation_price: Option<f64>,
+    /// |mark - liquidation| / mark
+    pub liquidation_distance: Option<f64>,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct AccountState {
+    pub wallet_balance: f64,
+    /// Realised PnL - fees + funding booked since `wallet_balance` was set
+    pub settled_pnl: f64,
+    pub unrealized_pnl: f64,
+    /// Wallet balance + settled PnL + unrealised PnL
+    pub equity: f64,
+    pub used_margin: f64,
+    pub free_margin: f64,
+    /// Used margin / equity (Infinity when equity is exhausted)
+    pub margin_usage: f64,
+    pub positions: Vec<PositionMargin>,
+}
+
+/// Margin impact of a prospective trade, used for the pre-trade risk facts.
+#[derive(Debug, Clone, Copy)]
+pub struct MarginProjection {
+    pub margin_usage: f64,
+    pub projected_margin_usage: f64,
+    /// Liquidation distance after the trade, only when it grows the position
+    pub liquidation_distance: Option<f64>,
+}
+
+/// Isolated-margin estimate: the position is liquidated once losses eat
+/// the initial margin down to the maintenance requirement.
+pub fn liquidation_price(quantity: f64, entry_price: f64, leverage: f64, maintenance_margin_rate: f64) -> Option<f64> {
+    if quantity == 0.0 || entry_price <= 0.0 {
+        return None;
+    }
+    let buffer = 1.0 / leverage - maintenance_margin_rate;
+    let price = if quantity > 0.0 {
+        entry_price * (1.0 - buffer)
+    } else {
+        entry_price * (1.0 + buffer)
+    };
+    (price > 0.0).then_some(price)
+}
+
+pub fn position_margin(position: &Position, leverage: f64, maintenance_margin_rate: f64) -> PositionMargin {
+    let quantity = if position.is_flat() { 0.0 } else { position.quantity };
+    let liquidation = liquidation_price(quantity, position.entry_price, leverage, maintenance_margin_rate);
+    let mark = position.current_price;
+
+    PositionMargin {
+        symbol: position.symbol.clone(),
+        quantity,
+        leverage,
+        used_margin: position.notional().abs() / leverage,
+        liquidation_price: liquidation,
+        liquidation_distance: liquidation.filter(|_| mark > 0.0).map(|liq| (mark - liq).abs() / mark),
+    }
+}
+
+pub fn account_state(
+    settings: &AccountSettings,
+    positions: &[Position],
+    leverage_of: impl Fn(&str) -> f64,
+) -> AccountState {
+    let margins: Vec<PositionMargin> = positions
+        .iter()
+        .map(|p| position_margin(p, leverage_of(&p.symbol), settings.maintenance_margin_rate))
+        .collect();
+
+    let unrealized_pnl: f64 = positions.iter().map(|p| p.pnl).sum();
+    let used_margin: f64 = margins.iter().map(|m| m.used_margin).sum();
+    let equity = equity(settings, positions);
+
+    AccountState {
+        wallet_balance: settings.wallet_balance,
+        settled_pnl: settled_pnl(settings, positions),
+        unrealized_pnl,
+        equity,
+        used_margin,
+        free_margin: equity - used_margin,
+        margin_usage: margin_usage(used_margin, equity),
+        positions: margins,
+    }
+}
+
+/// Wallet balance + PnL settled since it was set + unrealised PnL.
+pub fn equity(settings: &AccountSettings, positions: &[Position]) -> f64 {
+    settings.wallet_balance + settled_pnl(settings, positions) + positions.iter().map(|p| p.pnl).sum::<f64>()
+}
+
+/// Realised PnL - fees + funding booked since the wallet balance was set.
+pub fn settled_pnl(settings: &AccountSettings, positions: &[Position]) -> f64 {
+    positions.iter().map(Position::booked_pnl).sum::<f64>() - settings.booked_pnl_at_balance
+}
+
+/// Margin usage before and after trading `size` of `symbol` at `price`.
+pub fn project(
+    settings: &AccountSettings,
+    positions: &[Position],
+    leverage_of: impl Fn(&str) -> f64,
+    symbol: &str,
+    side: Side,
+    size: f64,
+    price: f64,
+) -> MarginProjection {
+    let before = account_state(settings, positions, &leverage_of);
+
+    let mut position = positions
+        .iter()
+        .find(|p| p.symbol == symbol)
+        .cloned()
+        .unwrap_or_else(|| Position {
+            symbol: symbol.to_string(),
+            ..Position::default()
+        });
+    let previous_qty = position.quantity.abs();
+    position.apply_fill(side, size, price, 0.0, Liquidity::Taker);
+
+    let leverage = leverage_of(symbol);
+    let margin = position_margin(&position, leverage, settings.maintenance_margin_rate);
+    let used_margin = before.used_margin
+        - before
+            .positions
+            .iter()
+            .filter(|m| m.symbol == symbol)
+            .map(|m| m.used_margin)
+            .sum::<f64>()
+        + margin.used_margin;
+
+    MarginProjection {
+        margin_usage: before.margin_usage,
+        projected_margin_usage: margin_usage(used_margin, before.equity),
+        liquidation_distance: margin
+            .liquidation_distance
+            .filter(|_| position.quantity.abs() > previous_qty),
+    }
+}
+
+fn margin_usage(used_margin: f64, equity: f64) -> f64 {
+    if used_margin <= 0.0 {
+        0.0
+    } else if equity <= 0.0 {
+        f64::INFINITY
+    } else {
+        used_margin / equity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn long_btc() -> Position {
+        let mut p = Position {
+            symbol: "BTC".to_string(),
+            ..Position::default()
+        };
+        p.apply_fill(Side::Buy, 1.0, 100.0, 0.0, Liquidity::Taker);
+        p
+    }
+
+    #[test]
+    fn test_liquidation_price_long_and_short() {
+        // 10x leaves a 10% buffer minus 0.5% maintenance
+        let long = liquidation_price(1.0, 100.0, 10.0, 0.005).unwrap();
+        let short = liquidation_price(-1.0, 100.0, 10.0, 0.005).unwrap();
+        assert!((long - 90.5).abs() < 1e-9);
+        assert!((short - 109.5).abs() < 1e-9);
+        // Unlevered longs cannot be liquidated
+        assert!(liquidation_price(1.0, 100.0, 1.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_account_equity_and_projection() {
+        let settings = AccountSettings {
+            wallet_balance: 50.0,
+            ..AccountSettings::default()
+        };
+        let mut btc = long_btc();
+        btc.current_price = 110.0;
+        btc.pnl = btc.unrealized_pnl();
+
+        let account = account_state(&settings, std::slice::from_ref(&btc), |_| 5.0);
+        assert_eq!(account.equity, 60.0);
+        assert_eq!(account.used_margin, 22.0);
+        assert_eq!(account.free_margin, 38.0);
+
+        // Doubling the long at 110 adds 22 of margin
+        let projection = project(&settings, &[btc.clone()], |_| 5.0, "BTC", Side::Buy, 1.0, 110.0);
+        assert!((projection.projected_margin_usage - 44.0 / 60.0).abs() < 1e-9);
+        assert!(projection.liquidation_distance.is_some());
+
+        // Reducing frees margin and does not report a liquidation distance
+        let projection = project(&settings, &[btc], |_| 5.0, "BTC", Side::Sell, 0.5, 110.0);
+        assert!(projection.projected_margin_usage < projection.margin_usage);
+        assert!(projection.liquidation_distance.is_none());
+    }
+}
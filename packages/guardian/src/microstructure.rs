@@ -0,0 +1,172 @@
+use crate::error::{GuardianError, Result};
+use napi_derive::napi;
+
+/// Number of book levels considered per side for multi-level features
+pub const FEATURE_DEPTH_LEVELS: usize = 5;
+/// Default exponential decay applied per level in depth-weighted pressure
+pub const DEFAULT_LEVEL_DECAY: f64 = 0.5;
+
+/// Microstructure features derived from a single order book snapshot.
+/// Bids are expected best-first (descending price), asks best-first (ascending).
+#[napi(object)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BookFeatures {
+    pub mid_price: f64,
+    pub spread: f64,
+    /// Size-weighted L1 price: (ask * bid_qty + bid * ask_qty) / (bid_qty + ask_qty)
+    pub microprice: f64,
+    /// Level-1 queue imbalance (-1.0 to 1.0)
+    pub queue_imbalance: f64,
+    /// Multi-level analogue of the microprice using side VWAPs and depths
+    pub weighted_mid: f64,
+    /// Depth imbalance with exp(-decay * level) weights (-1.0 to 1.0)
+    pub depth_pressure: f64,
+    /// Cumulative bid volume per unit of distance from mid
+    pub bid_slope: f64,
+    /// Cumulative ask volume per unit of distance from mid
+    pub ask_slope: f64,
+    /// Average of both side slopes (overall book steepness)
+    pub book_slope: f64,
+}
+
+/// Parse `[[price, size], ...]` levels, dropping empty (zero-size) ones.
+/// Malformed JSON, non-positive prices and negative sizes are rejected.
+pub fn parse_levels(json: &str) -> Result<Vec<(f64, f64)>> {
+    let levels: Vec<(f64, f64)> = serde_json::from_str(json)
+        .map_err(|e| GuardianError::Validation(format!("Malformed order book levels: {}", e)))?;
+    if let Some((p, v)) = levels
+        .iter()
+        .find(|(p, v)| !(p.is_finite() && v.is_finite() && *p > 0.0 && *v >= 0.0))
+    {
+        return Err(GuardianError::Validation(format!("Invalid order book level [{}, {}]", p, v)));
+    }
+    Ok(levels.into_iter().filter(|(_p, v)| *v > 0.0).collect())
+}
+
+/// Decay must be finite and non-negative (0 weights every level equally).
+pub fn validate_level_decay(level_decay: f64) -> Result<()> {
+    if level_decay.is_finite() && level_decay >= 0.0 {
+        Ok(())
+    } else {
+        Err(GuardianError::Validation(format!("Level decay must be non-negative, got {}", level_decay)))
+    }
+}
+
+pub fn compute_features(bids: &[(f64, f64)], asks: &[(f64, f64)], level_decay: f64) -> BookFeatures {
+    let (Some(&(best_bid, bid_qty)), Some(&(best_ask, ask_qty))) = (bids.first(), asks.first()) else {
+        return BookFeatures::default();
+    };
+
+    let mid_price = (best_bid + best_ask) / 2.0;
+    let l1_total = bid_qty + ask_qty;
+
+    let microprice = (best_ask * bid_qty + best_bid * ask_qty) / l1_total;
+    let queue_imbalance = (bid_qty - ask_qty) / l1_total;
+
+    let bids = &bids[..bids.len().min(FEATURE_DEPTH_LEVELS)];
+    let asks = &asks[..asks.len().min(FEATURE_DEPTH_LEVELS)];
+
+    // Weighted mid: each side's VWAP pulled toward the side with less depth
+    let bid_depth: f64 = bids.iter().map(|(_p, v)| v).sum();
+    let ask_depth: f64 = asks.iter().map(|(_p, v)| v).sum();
+    let bid_vwap = bids.iter().map(|(p, v)| p * v).sum::<f64>() / bid_depth;
+    let ask_vwap = asks.iter().map(|(p, v)| p * v).sum::<f64>() / ask_depth;
+    let weighted_mid = (bid_vwap * ask_depth + ask_vwap * bid_depth) / (bid_depth + ask_depth);
+
+    // Depth pressure: near-touch levels dominate via exponential decay
+    let decayed = |levels: &[(f64, f64)]| -> f64 {
+        levels
+            .iter()
+            .enumerate()
+            .map(|(i, (_p, v))| v * (-level_decay * i as f64).exp())
+            .sum()
+    };
+    let weighted_bid = decayed(bids);
+    let weighted_ask = decayed(asks);
+    let depth_pressure = if weighted_bid + weighted_ask > 0.0 {
+        (weighted_bid - weighted_ask) / (weighted_bid + weighted_ask)
+    } else {
+        0.0
+    };
+
+    let bid_slope = side_slope(bids, mid_price);
+    let ask_slope = side_slope(asks, mid_price);
+
+    BookFeatures {
+        mid_price,
+        spread: best_ask - best_bid,
+        microprice,
+        queue_imbalance,
+        weighted_mid,
+        depth_pressure,
+        bid_slope,
+        ask_slope,
+        book_slope: (bid_slope + ask_slope) / 2.0,
+    }
+}
+
+/// Least-squares slope (through the origin) of cumulative volume vs distance from mid.
+fn side_slope(levels: &[(f64, f64)], mid: f64) -> f64 {
+    let mut cumulative = 0.0;
+    let mut sum_xy = 0.0;
+    let mut sum_xx = 0.0;
+
+    for (price, volume) in levels {
+        cumulative += volume;
+        let distance = (price - mid).abs();
+        sum_xy += distance * cumulative;
+        sum_xx += distance * distance;
+    }
+
+    if sum_xx > 0.0 {
+        sum_xy / sum_xx
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_microprice_leans_toward_thin_side() {
+        // Heavy bid queue -> fair price sits closer to the ask
+        let bids = [(99.0, 9.0)];
+        let asks = [(101.0, 1.0)];
+        let f = compute_features(&bids, &asks, DEFAULT_LEVEL_DECAY);
+
+        assert_eq!(f.mid_price, 100.0);
+        assert!((f.microprice - 100.8).abs() < 1e-9);
+        assert!((f.queue_imbalance - 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_symmetric_book_is_neutral() {
+        let bids = [(99.0, 2.0), (98.0, 4.0), (97.0, 6.0)];
+        let asks = [(101.0, 2.0), (102.0, 4.0), (103.0, 6.0)];
+        let f = compute_features(&bids, &asks, DEFAULT_LEVEL_DECAY);
+
+        assert!((f.weighted_mid - 100.0).abs() < 1e-9);
+        assert!(f.depth_pressure.abs() < 1e-9);
+        assert!((f.bid_slope - f.ask_slope).abs() < 1e-9);
+        assert!(f.book_slope > 0.0);
+    }
+
+    #[test]
+    fn test_empty_side_returns_default() {
+        let f = compute_features(&[(99.0, 1.0)], &[], DEFAULT_LEVEL_DECAY);
+        assert_eq!(f, BookFeatures::default());
+    }
+
+    #[test]
+    fn test_malformed_levels_are_rejected() {
+        assert_eq!(parse_levels("[[99.0, 1.0], [98.0, 0.0]]").unwrap(), vec![(99.0, 1.0)]);
+        for json in ["", "{\"bids\": []}", "[[99.0, -1.0]]", "[[0.0, 1.0]]"] {
+            assert_eq!(parse_levels(json).unwrap_err().kind(), "VALIDATION", "{}", json);
+        }
+        assert!(validate_level_decay(0.0).is_ok());
+        assert!(validate_level_decay(f64::NAN).is_err());
+        assert!(validate_level_decay(-0.5).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStatus {
+    New,
+    Acknowledged,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    Rejected,
+}
+
+impl OrderStatus {
+    pub fn parse(status: &str) -> Option<OrderStatus> {
+        match status.to_ascii_uppercase().as_str() {
+            "NEW" => Some(OrderStatus::New),
+            "ACKNOWLEDGED" => Some(OrderStatus::Acknowledged),
+            "PARTIALLY_FILLED" => Some(OrderStatus::PartiallyFilled),
+            "FILLED" => Some(OrderStatus::Filled),
+            "CANCELLED" => Some(OrderStatus::Cancelled),
+            "REJECTED" => Some(OrderStatus::Rejected),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrderStatus::New => "NEW",
+            OrderStatus::Acknowledged => "ACKNOWLEDGED",
+            OrderStatus::PartiallyFilled => "PARTIALLY_FILLED",
+            OrderStatus::Filled => "FILLED",
+            OrderStatus::Cancelled => "CANCELLED",
+            OrderStatus::Rejected => "REJECTED",
+        }
+    }
+
+    /// Still able to trade (counts towards open orders).
+    pub fn is_open(self) -> bool {
+        matches!(
+            self,
+            OrderStatus::New | OrderStatus::Acknowledged | OrderStatus::PartiallyFilled
+        )
+    }
+
+    /// Allowed moves. Fills may arrive before the acknowledgement or after
+    /// our cancel (the exchange executed first), and a rejection is only
+    /// possible before the exchange accepted the order.
+    pub fn can_become(self, next: OrderStatus) -> bool {
+        use OrderStatus::*;
+        matches!(
+            (self, next),
+            (New, Acknowledged | Rejected)
+                | (New | Acknowledged | PartiallyFilled, PartiallyFilled | Filled | Cancelled)
+                | (Cancelled, PartiallyFilled | Filled)
+        )
+    }
+
+    /// An acknowledgement arriving after fills already moved the order on.
+    pub fn is_late_ack(self, next: OrderStatus) -> bool {
+        next == OrderStatus::Acknowledged && matches!(self, OrderStatus::PartiallyFilled | OrderStatus::Filled)
+    }
+}
+
+/// An order submitted by the bot, as tracked by the guardian.
+#[napi(object)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Order {
+    pub id: String,
+    pub symbol: String,
+    /// "BUY" or "SELL"
+    pub side: String,
+    pub quantity: f64,
+    /// Limit price, None for market orders
+    pub price: Option<f64>,
+    pub filled_quantity: f64,
+    pub average_fill_price: f64,
+    /// "NEW", "ACKNOWLEDGED", "PARTIALLY_FILLED", "FILLED", "CANCELLED" or "REJECTED"
+    pub status: String,
+    /// Rejection or cancellation reason
+    pub reason: Option<String>,
+    pub strategy_id: Option<String>,
+    pub signal_hash: Option<String>,
+    /// "MathGuardian", "NeuralCortex" or "SymbolicConsensus"
+    pub signal_source: Option<String>,
+    pub created_ms: i64,
+    pub updated_ms: i64,
+}
+
+impl Order {
+    pub fn status(&self) -> OrderStatus {
+        OrderStatus::parse(&self.status).unwrap_or(OrderStatus::New)
+    }
+
+    pub fn remaining(&self) -> f64 {
+        (self.quantity - self.filled_quantity).max(0.0)
+    }
+
+    /// Notional still resting on the book (0.0 for market and closed orders).
+    pub fn resting_notional(&self) -> f64 {
+        match self.price {
+            Some(price) if self.status().is_open() => self.remaining() * price,
+            _ => 0.0,
+        }
+    }
+}
+
+/// New order to track. `id` should be the client order id sent to the exchange.
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct OrderInput {
+    pub id: String,
+    pub symbol: String,
+    pub side: String,
+    pub quantity: f64,
+    /// Limit price; omit for market orders
+    pub price: Option<f64>,
+    pub strategy_id: Option<String>,
+    pub signal_hash: Option<String>,
+    /// Tags the order's fills for per-source attribution
+    pub signal_source: Option<String>,
+}
+
+/// Open orders and resting notional for one symbol, used as risk facts.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrderExposure {
+    pub open_orders: u32,
+    pub resting_notional: f64,
+}
+
+pub fn exposure<'a>(orders: impl IntoIterator<Item = &'a Order>) -> OrderExposure {
+    orders
+        .into_iter()
+        .filter(|o| o.status().is_open())
+        .fold(OrderExposure::default(), |mut exposure, order| {
+            exposure.open_orders += 1;
+            exposure.resting_notional += order.resting_notional();
+            exposure
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions() {
+        use OrderStatus::*;
+        assert!(New.can_become(Acknowledged));
+        assert!(New.can_become(Filled));
+        assert!(Acknowledged.can_become(Cancelled));
+        assert!(PartiallyFilled.can_become(PartiallyFilled));
+        assert!(!Acknowledged.can_become(Rejected));
+        assert!(!Filled.can_become(Cancelled));
+        assert!(!Cancelled.can_become(Acknowledged));
+        assert!(Cancelled.can_become(Filled));
+        assert!(PartiallyFilled.is_late_ack(Acknowledged));
+        assert!(!New.is_late_ack(Acknowledged));
+        assert!(!Rejected.is_open());
+    }
+}
@@ -0,0 +1,241 @@
+use crate::error::{GuardianError, Result};
+use crate::state::{PortfolioState, Position};
+use napi_derive::napi;
+use std::collections::BTreeMap;
+
+/// Quantity difference tolerated before reporting drift
+pub const DEFAULT_QUANTITY_TOLERANCE: f64 = 1e-8;
+/// Entry price difference tolerated before reporting drift (basis points)
+pub const DEFAULT_ENTRY_PRICE_TOLERANCE_BPS: f64 = 5.0;
+
+/// A position as reported by the exchange.
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct ExchangePosition {
+    pub symbol: String,
+    /// Signed quantity: positive long, negative short
+    pub quantity: f64,
+    pub entry_price: f64,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct ReconcileOptions {
+    pub quantity_tolerance: Option<f64>,
+    pub entry_price_tolerance_bps: Option<f64>,
+    /// Overwrite guardian positions with exchange values (recorded in the ledger)
+    pub adopt_exchange: Option<bool>,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct PositionMismatch {
+    pub symbol: String,
+    /// "MISSING" (exchange only), "EXTRA" (guardian only),
+    /// "QUANTITY_DRIFT" or "ENTRY_PRICE_DRIFT"
+    pub kind: String,
+    pub guardian_quantity: f64,
+    pub exchange_quantity: f64,
+    pub guardian_entry_price: f64,
+    pub exchange_entry_price: f64,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct ReconcileReport {
+    pub mismatches: Vec<PositionMismatch>,
+    pub in_sync: bool,
+    /// Symbols whose guardian position was replaced by the exchange values
+    pub adjusted_symbols: Vec<String>,
+}
+
+/// Compare guardian positions with the exchange snapshot, optionally adopting
+/// the exchange side for every mismatched symbol.
+pub fn reconcile(
+    state: &PortfolioState,
+    exchange: &[ExchangePosition],
+    options: &ReconcileOptions,
+    timestamp_ms: i64,
+) -> Result<ReconcileReport> {
+    // A flat position may report no entry price; anything held needs one
+    let valid = |p: &&ExchangePosition| {
+        p.quantity.is_finite() && (p.quantity == 0.0 || (p.entry_price.is_finite() && p.entry_price > 0.0))
+    };
+    if let Some(bad) = exchange.iter().find(|p| !valid(p)) {
+        return Err(GuardianError::Validation(format!(
+            "Exchange position {} needs a finite quantity and a positive entry price",
+            bad.symbol
+        )));
+    }
+    let qty_tolerance = options.quantity_tolerance.unwrap_or(DEFAULT_QUANTITY_TOLERANCE);
+    let price_tolerance_bps = options
+        .entry_price_tolerance_bps
+        .unwrap_or(DEFAULT_ENTRY_PRICE_TOLERANCE_BPS);
+    let valid_tolerance = |t: f64| t.is_finite() && t >= 0.0;
+    if !(valid_tolerance(qty_tolerance) && valid_tolerance(price_tolerance_bps)) {
+        return Err(GuardianError::Validation("Reconcile tolerances must be finite and non-negative".to_string()));
+    }
+
+    // Sorted for a deterministic report; later duplicates win
+    let mut theirs: BTreeMap<&str, &ExchangePosition> = BTreeMap::new();
+    for position in exchange.iter().filter(|p| p.quantity.abs() > qty_tolerance) {
+        theirs.insert(position.symbol.as_str(), position);
+    }
+    let ours: BTreeMap<String, Position> = state
+        .positions()?
+        .into_iter()
+        .filter(|p| p.quantity.abs() > qty_tolerance)
+        .map(|p| (p.symbol.clone(), p))
+        .collect();
+
+    let mut symbols: Vec<&str> = theirs.keys().copied().collect();
+    symbols.extend(ours.keys().map(String::as_str).filter(|s| !theirs.contains_key(s)));
+    symbols.sort_unstable();
+
+    let mut mismatches = Vec::new();
+    for symbol in symbols {
+        let guardian = ours.get(symbol);
+        let exchange = theirs.get(symbol);
+        let mismatch = |kind: &str| PositionMismatch {
+            symbol: symbol.to_string(),
+            kind: kind.to_string(),
+            guardian_quantity: guardian.map_or(0.0, |p| p.quantity),
+            exchange_quantity: exchange.map_or(0.0, |p| p.quantity),
+            guardian_entry_price: guardian.map_or(0.0, |p| p.entry_price),
+            exchange_entry_price: exchange.map_or(0.0, |p| p.entry_price),
+        };
+
+        match (guardian, exchange) {
+            (None, Some(_)) => mismatches.push(mismatch("MISSING")),
+            (Some(_), None) => mismatches.push(mismatch("EXTRA")),
+            (Some(g), Some(e)) => {
+                if (g.quantity - e.quantity).abs() > qty_tolerance {
+                    mismatches.push(mismatch("QUANTITY_DRIFT"));
+                }
+                let drift_bps = (g.entry_price - e.entry_price).abs() / e.entry_price.abs().max(f64::EPSILON) * 10_000.0;
+                if drift_bps > price_tolerance_bps {
+                    mismatches.push(mismatch("ENTRY_PRICE_DRIFT"));
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    let mut adjusted_symbols: Vec<String> = Vec::new();
+    if options.adopt_exchange.unwrap_or(false) {
+        let mut targets: Vec<ExchangePosition> = Vec::new();
+        for m in &mismatches {
+            if !targets.iter().any(|t| t.symbol == m.symbol) {
+                targets.push(ExchangePosition {
+                    symbol: m.symbol.clone(),
+                    quantity: m.exchange_quantity,
+                    entry_price: m.exchange_entry_price,
+                });
+            }
+        }
+        // One commit for the whole reconciliation
+        state.adopt_positions(&targets, &format!("reconcile-{}", timestamp_ms))?;
+        adjusted_symbols = targets.into_iter().map(|t| t.symbol).collect();
+    }
+
+    Ok(ReconcileReport {
+        in_sync: mismatches.is_empty(),
+        mismatches,
+        adjusted_symbols,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{FillInput, Side};
+
+    fn exchange(symbol: &str, quantity: f64, entry_price: f64) -> ExchangePosition {
+        ExchangePosition {
+            symbol: symbol.to_string(),
+            quantity,
+            entry_price,
+        }
+    }
+
+    #[test]
+    fn test_reports_and_adopts_mismatches() {
+        let state = PortfolioState::new();
+        state.record_fill(FillInput::new("BTC", Side::Buy, 1.0, 100.0, 0.0)).unwrap();
+        state.record_fill(FillInput::new("ETH", Side::Sell, 2.0, 50.0, 0.0)).unwrap();
+        state.record_fill(FillInput::new("LTC", Side::Buy, 5.0, 80.0, 0.0)).unwrap();
+
+        let snapshot = [
+            exchange("BTC", 1.0, 100.01), // within 5bps
+            exchange("ETH", -3.0, 50.0),
+            exchange("SOL", 10.0, 20.0),
+        ];
+
+        let report = reconcile(&state, &snapshot, &ReconcileOptions::default(), 0).unwrap();
+        let kinds: Vec<(&str, &str)> = report
+            .mismatches
+            .iter()
+            .map(|m| (m.symbol.as_str(), m.kind.as_str()))
+            .collect();
+        assert_eq!(kinds, vec![("ETH", "QUANTITY_DRIFT"), ("LTC", "EXTRA"), ("SOL", "MISSING")]);
+        assert!(report.adjusted_symbols.is_empty());
+
+        let options = ReconcileOptions {
+            adopt_exchange: Some(true),
+            ..ReconcileOptions::default()
+        };
+        let report = reconcile(&state, &snapshot, &options, 1).unwrap();
+        assert_eq!(report.adjusted_symbols.len(), 3);
+
+        assert!(reconcile(&state, &snapshot, &ReconcileOptions::default(), 2).unwrap().in_sync);
+        assert_eq!(state.position("ETH").unwrap().unwrap().realized_pnl, 0.0);
+        assert!(state.rebuild_from_ledger().unwrap().matches);
+        // The adoption landed as a single commit
+        assert_eq!(state.commit_history().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_repeated_adoption_at_one_timestamp_books_every_fill() {
+        let state = PortfolioState::new();
+        let options = ReconcileOptions {
+            adopt_exchange: Some(true),
+            ..ReconcileOptions::default()
+        };
+
+        reconcile(&state, &[exchange("BTC", 1.0, 100.0)], &options, 7).unwrap();
+        let report = reconcile(&state, &[exchange("BTC", 2.0, 110.0)], &options, 7).unwrap();
+
+        assert_eq!(report.adjusted_symbols, vec!["BTC".to_string()]);
+        let btc = state.position("BTC").unwrap().unwrap();
+        assert_eq!((btc.quantity, btc.entry_price), (2.0, 110.0));
+        assert!(state.rebuild_from_ledger().unwrap().matches);
+    }
+
+    #[test]
+    fn test_exchange_position_without_entry_price_is_rejected() {
+        let state = PortfolioState::new();
+        let snapshot = [exchange("BTC", 1.0, 0.0)];
+        let error = reconcile(&state, &snapshot, &ReconcileOptions::default(), 0).unwrap_err();
+        assert_eq!(error.kind(), "VALIDATION");
+        // Flat entries need no price
+        assert!(reconcile(&state, &[exchange("ETH", 0.0, 0.0)], &ReconcileOptions::default(), 0).is_ok());
+    }
+
+    #[test]
+    fn test_bad_tolerances_are_rejected() {
+        let state = PortfolioState::new();
+        let snapshot = [exchange("BTC", 1.0, 100.0)];
+        let cases = [(Some(-1.0), None), (Some(f64::NAN), None), (None, Some(f64::INFINITY)), (None, Some(-5.0))];
+        for (quantity, bps) in cases {
+            let options = ReconcileOptions {
+                quantity_tolerance: quantity,
+                entry_price_tolerance_bps: bps,
+                adopt_exchange: Some(true),
+            };
+            let error = reconcile(&state, &snapshot, &options, 0).unwrap_err();
+            assert_eq!(error.kind(), "VALIDATION");
+        }
+        // Nothing adopted on rejection
+        assert!(state.position("BTC").unwrap().is_none());
+    }
+}
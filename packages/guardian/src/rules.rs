@@ -23,6 +23,42 @@ crepe! {
     @input
     struct PositionCount(pub i64); // Number of open positions for symbol
 
+    @input
+    struct WallSpoofScore<'a>(pub &'a str, pub i64); // Side ("BID"/"ASK"), spoof likelihood (x1000)
+
+    @input
+    struct Vpin(pub i64); // VPIN toxicity (x1000). 0.70 -> 700
+
+    @input
+    struct FlowImbalance(pub i64); // Aggressor flow imbalance (x1000). -1000 to 1000
+
+    @input
+    struct MarginUsage(pub i64); // Used margin / equity now (x1000). 0.80 -> 800
+
+    @input
+    struct ProjectedMarginUsage(pub i64); // Used margin / equity after the trade (x1000)
+
+    @input
+    struct MaxMarginUsage(pub i64); // Configured limit (x1000)
+
+    @input
+    struct LiquidationDistance(pub i64); // |mark - liquidation| / mark after the trade (x1000)
+
+    @input
+    struct MinLiquidationDistance(pub i64); // Configured limit (x1000). 5% -> 50
+
+    @input
+    struct OpenOrders(pub i64); // Open (unfilled or partially filled) orders for symbol
+
+    @input
+    struct RestingNotional(pub i64); // Resting limit-order notional for symbol (x100)
+
+    @input
+    struct AccountEquity(pub i64); // Wallet balance + unrealised PnL (x100)
+
+    @input
+    struct PositionEffect<'a>(pub &'a str); // "OPEN" (new or added exposure) or "REDUCE"
+
     @output
     pub struct BlockTrade<'a>(pub &'a str); // Reason
 
@@ -37,16 +73,22 @@ crepe! {
         (v > 850);
 
     // Invariant 2: OFI Divergence (Buying into Sell Wall)
+    // Ignored when the ask wall looks spoofed (score >= 0.60)
     BlockTrade("OFI Divergence: Buying into massive Sell Wall") <- 
         TradeIntent("BUY", _), 
         OfiScore(s), 
-        (s < -300); // -0.30 in real terms
+        WallSpoofScore("ASK", p),
+        (s < -300), // -0.30 in real terms
+        (p < 600);
 
     // Invariant 3: OFI Divergence (Selling into Buy Wall)
+    // Ignored when the bid wall looks spoofed (score >= 0.60)
     BlockTrade("OFI Divergence: Selling into massive Buy Wall") <- 
         TradeIntent("SELL", _), 
         OfiScore(s), 
-        (s > 300); // +0.30 in real terms
+        WallSpoofScore("BID", p),
+        (s > 300), // +0.30 in real terms
+        (p < 600);
     
     // Invariant 4: Max Position Limit (2 per symbol)
     BlockTrade("Max Positions Reached: Limit is 2 per symbol") <-
@@ -73,8 +115,93 @@ crepe! {
         RsiValue(rsi),
         (adx > 2500),  // ADX > 25
         (rsi > 2500);  // RSI > 25
+
+    // Invariant 7: Toxic Order Flow (informed traders active)
+    // Reducing trades are exempt: getting out of toxic flow is the point
+    BlockTrade("Toxic Flow: VPIN above 0.70 blocks new entries") <-
+        TradeIntent(_, _),
+        PositionEffect("OPEN"),
+        Vpin(v),
+        (v > 700);
+
+    // Invariant 8: Elevated toxicity with aggressors on the other side
+    BlockTrade("Toxic Flow: BUY against aggressive selling") <-
+        TradeIntent("BUY", _),
+        Vpin(v),
+        FlowImbalance(f),
+        (v > 500),
+        (f < -500);
+
+    BlockTrade("Toxic Flow: SELL against aggressive buying") <-
+        TradeIntent("SELL", _),
+        Vpin(v),
+        FlowImbalance(f),
+        (v > 500),
+        (f > 500);
+
+    // Invariant 9: Margin Limit (trades that free margin are always allowed)
+    BlockTrade("Margin Limit: trade pushes margin usage above limit") <-
+        TradeIntent(_, _),
+        MarginUsage(now),
+        ProjectedMarginUsage(after),
+        MaxMarginUsage(limit),
+        (after > limit),
+        (after > now);
+
+    // Invariant 10: Liquidation Buffer (only reported for trades growing the position)
+    BlockTrade("Liquidation Buffer: liquidation price too close to mark") <-
+        TradeIntent(_, _),
+        LiquidationDistance(d),
+        MinLiquidationDistance(min),
+        (d < min);
+
+    // Invariant 11: Max Open Orders (3 per symbol, reducing trades exempt)
+    BlockTrade("Max Open Orders: Limit is 3 per symbol") <-
+        TradeIntent(_, _),
+        PositionEffect("OPEN"),
+        OpenOrders(n),
+        (n >= 3);
+
+    // Invariant 12: Resting orders already commit more than the account equity
+    // (reducing trades exempt)
+    BlockTrade("Resting Exposure: open orders exceed account equity") <-
+        TradeIntent(_, _),
+        PositionEffect("OPEN"),
+        RestingNotional(r),
+        AccountEquity(e),
+        (e > 0),
+        (r > e);
 }
 
+/// Facts derived from guardian state for a specific symbol.
+/// Defaults describe a neutral market (no extra evidence).
+#[derive(Debug, Clone, Default)]
+pub struct MarketFacts {
+    pub bid_wall_spoof: f64,
+    pub ask_wall_spoof: f64,
+    /// VPIN and aggressor imbalance, only once enough buckets completed
+    pub vpin: Option<f64>,
+    pub flow_imbalance: Option<f64>,
+    /// Margin impact of the intent, when the symbol has a known mark
+    pub margin: Option<MarginFacts>,
+    pub open_orders: i64,
+    pub resting_notional: f64,
+    /// Account equity, when a wallet balance has been configured
+    pub equity: Option<f64>,
+    /// The intent only shrinks the current position (no flip)
+    pub reduces_position: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MarginFacts {
+    pub margin_usage: f64,
+    pub projected_margin_usage: f64,
+    pub max_margin_usage: f64,
+    pub liquidation_distance: Option<f64>,
+    pub min_liquidation_distance: f64,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn check_risk(
     side: &str, 
     size: f64, 
@@ -84,6 +211,21 @@ pub fn check_risk(
     adx: f64,
     rsi: f64,
     position_count: i64
+) -> Option<String> {
+    check_risk_with_facts(side, size, vol, ofi, trend, adx, rsi, position_count, &MarketFacts::default())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn check_risk_with_facts(
+    side: &str, 
+    size: f64, 
+    vol: f64, 
+    ofi: f64, 
+    trend: &str,
+    adx: f64,
+    rsi: f64,
+    position_count: i64,
+    facts: &MarketFacts,
 ) -> Option<String> {
     let mut runtime = Crepe::new();
 
@@ -115,6 +257,39 @@ pub fn check_risk(
     let pos_fact = [PositionCount(position_count)];
     runtime.extend(&pos_fact);
 
+    let spoof_facts = [
+        WallSpoofScore("BID", (facts.bid_wall_spoof * 1000.0) as i64),
+        WallSpoofScore("ASK", (facts.ask_wall_spoof * 1000.0) as i64),
+    ];
+    runtime.extend(&spoof_facts);
+
+    if let Some(vpin) = facts.vpin {
+        runtime.extend([Vpin((vpin * 1000.0) as i64)]);
+    }
+    if let Some(imbalance) = facts.flow_imbalance {
+        runtime.extend([FlowImbalance((imbalance * 1000.0) as i64)]);
+    }
+
+    if let Some(margin) = &facts.margin {
+        // Saturating casts: exhausted equity reports usage as infinite
+        runtime.extend([MarginUsage((margin.margin_usage * 1000.0) as i64)]);
+        runtime.extend([ProjectedMarginUsage((margin.projected_margin_usage * 1000.0) as i64)]);
+        runtime.extend([MaxMarginUsage((margin.max_margin_usage * 1000.0) as i64)]);
+        runtime.extend([MinLiquidationDistance((margin.min_liquidation_distance * 1000.0) as i64)]);
+        if let Some(distance) = margin.liquidation_distance {
+            runtime.extend([LiquidationDistance((distance * 1000.0) as i64)]);
+        }
+    }
+
+    runtime.extend([OpenOrders(facts.open_orders)]);
+    runtime.extend([RestingNotional((facts.resting_notional * 100.0) as i64)]);
+    if let Some(equity) = facts.equity {
+        runtime.extend([AccountEquity((equity * 100.0) as i64)]);
+    }
+
+    let effect = if facts.reduces_position { "REDUCE" } else { "OPEN" };
+    runtime.extend([PositionEffect(effect)]);
+
     let (blocks,) = runtime.run();
 
     if let Some(block) = blocks.into_iter().next() {
@@ -122,3 +297,144 @@ pub fn check_risk(
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Calm, trendless market: only the facts under test can block
+    fn check(side: &str, ofi: f64, facts: MarketFacts) -> Option<String> {
+        check_risk_with_facts(side, 1.0, 0.1, ofi, "SIDEWAYS", 10.0, 50.0, 0, &facts)
+    }
+
+    fn margin(usage: f64, projected: f64, distance: Option<f64>) -> Option<MarginFacts> {
+        Some(MarginFacts {
+            margin_usage: usage,
+            projected_margin_usage: projected,
+            max_margin_usage: 0.8,
+            liquidation_distance: distance,
+            min_liquidation_distance: 0.05,
+        })
+    }
+
+    #[test]
+    fn test_neutral_facts_pass() {
+        let facts = MarketFacts {
+            vpin: Some(0.3),
+            flow_imbalance: Some(0.1),
+            margin: margin(0.2, 0.3, Some(0.2)),
+            open_orders: 1,
+            resting_notional: 100.0,
+            equity: Some(1_000.0),
+            ..MarketFacts::default()
+        };
+        assert_eq!(check("BUY", 0.0, facts.clone()), None);
+        assert_eq!(check("SELL", 0.0, facts), None);
+    }
+
+    #[test]
+    fn test_each_rule_blocks() {
+        let cases: Vec<(&str, &str, f64, MarketFacts, Option<&str>)> = vec![
+            // Walls: a trusted wall blocks, a likely spoof is discounted
+            ("ask wall", "BUY", -0.5, MarketFacts::default(), Some("Buying into massive Sell Wall")),
+            ("spoofed ask wall", "BUY", -0.5, MarketFacts { ask_wall_spoof: 0.7, ..MarketFacts::default() }, None),
+            ("bid wall", "SELL", 0.5, MarketFacts::default(), Some("Selling into massive Buy Wall")),
+            ("spoofed bid wall", "SELL", 0.5, MarketFacts { bid_wall_spoof: 0.6, ..MarketFacts::default() }, None),
+            // Toxic flow
+            ("vpin", "BUY", 0.0, MarketFacts { vpin: Some(0.75), ..MarketFacts::default() }, Some("VPIN above 0.70")),
+            (
+                "buy into selling",
+                "BUY",
+                0.0,
+                MarketFacts { vpin: Some(0.6), flow_imbalance: Some(-0.6), ..MarketFacts::default() },
+                Some("BUY against aggressive selling"),
+            ),
+            (
+                "sell into buying",
+                "SELL",
+                0.0,
+                MarketFacts { vpin: Some(0.6), flow_imbalance: Some(0.6), ..MarketFacts::default() },
+                Some("SELL against aggressive buying"),
+            ),
+            (
+                "with the flow",
+                "SELL",
+                0.0,
+                MarketFacts { vpin: Some(0.6), flow_imbalance: Some(-0.6), ..MarketFacts::default() },
+                None,
+            ),
+            // Margin and liquidation buffer
+            (
+                "margin limit",
+                "BUY",
+                0.0,
+                MarketFacts { margin: margin(0.5, 0.9, None), ..MarketFacts::default() },
+                Some("Margin Limit"),
+            ),
+            (
+                "freeing margin",
+                "SELL",
+                0.0,
+                MarketFacts { margin: margin(0.95, 0.85, None), ..MarketFacts::default() },
+                None,
+            ),
+            (
+                "liquidation buffer",
+                "BUY",
+                0.0,
+                MarketFacts { margin: margin(0.2, 0.3, Some(0.03)), ..MarketFacts::default() },
+                Some("Liquidation Buffer"),
+            ),
+            // Open orders and resting exposure
+            ("open orders", "BUY", 0.0, MarketFacts { open_orders: 3, ..MarketFacts::default() }, Some("Max Open Orders")),
+            (
+                "resting exposure",
+                "BUY",
+                0.0,
+                MarketFacts { resting_notional: 1_500.0, equity: Some(1_000.0), ..MarketFacts::default() },
+                Some("Resting Exposure"),
+            ),
+            (
+                "closing in toxic flow",
+                "SELL",
+                0.0,
+                MarketFacts { vpin: Some(0.9), reduces_position: true, ..MarketFacts::default() },
+                None,
+            ),
+            (
+                "closing with orders resting",
+                "SELL",
+                0.0,
+                MarketFacts {
+                    open_orders: 5,
+                    resting_notional: 1_500.0,
+                    equity: Some(1_000.0),
+                    reduces_position: true,
+                    ..MarketFacts::default()
+                },
+                None,
+            ),
+            (
+                "no equity configured",
+                "BUY",
+                0.0,
+                MarketFacts { resting_notional: 1_500.0, ..MarketFacts::default() },
+                None,
+            ),
+        ];
+
+        for (name, side, ofi, facts, expected) in cases {
+            let verdict = check(side, ofi, facts);
+            match expected {
+                Some(reason) => assert!(
+                    verdict.as_deref().is_some_and(|v| v.contains(reason)),
+                    "{}: expected '{}', got {:?}",
+                    name,
+                    reason,
+                    verdict
+                ),
+                None => assert_eq!(verdict, None, "{}", name),
+            }
+        }
+    }
+}
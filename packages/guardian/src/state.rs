@@ -1,62 +1,2183 @@
 
-use loro::LoroDoc;
+use crate::analytics::EquityPoint;
+use crate::attribution::{self, AttributionReport};
+use crate::error::{self, GuardianError, Result};
+use crate::events::{ChangeFeed, Listener};
+use crate::margin::{self, AccountConfig, AccountSettings, AccountState, MarginProjection};
+use crate::orders::{self, Order, OrderExposure, OrderInput, OrderStatus};
+use crate::reconcile::ExchangePosition;
+use loro::{
+    Container, ExportMode, Frontiers, LoroDoc, LoroMap, LoroValue, Subscription, ValueOrContainer, VersionVector,
+};
+use napi_derive::napi;
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Quantities below this are treated as flat
+const QTY_EPSILON: f64 = 1e-12;
+/// Periodic checkpoint interval when none is given
+pub const DEFAULT_CHECKPOINT_INTERVAL_MS: u32 = 5_000;
+/// Equity points driven by price marks alone are merged within this window
+const EQUITY_SAMPLE_MS: i64 = 60_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    pub fn parse(side: &str) -> Option<Side> {
+        match side.to_ascii_uppercase().as_str() {
+            "BUY" | "LONG" => Some(Side::Buy),
+            "SELL" | "SHORT" => Some(Side::Sell),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Side::Buy => "BUY",
+            Side::Sell => "SELL",
+        }
+    }
+
+    fn sign(self) -> f64 {
+        match self {
+            Side::Buy => 1.0,
+            Side::Sell => -1.0,
+        }
+    }
+}
+
+/// Which side of the book a fill took. Unknown liquidity is charged as taker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Liquidity {
+    Maker,
+    #[default]
+    Taker,
+}
+
+impl Liquidity {
+    pub fn parse(liquidity: &str) -> Option<Liquidity> {
+        match liquidity.to_ascii_uppercase().as_str() {
+            "MAKER" => Some(Liquidity::Maker),
+            "TAKER" => Some(Liquidity::Taker),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Liquidity::Maker => "MAKER",
+            Liquidity::Taker => "TAKER",
+        }
+    }
+}
+
+/// Engine that produced the TradingSignal behind an order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalSource {
+    MathGuardian,
+    NeuralCortex,
+    SymbolicConsensus,
+}
+
+impl SignalSource {
+    pub fn parse(source: &str) -> Option<SignalSource> {
+        match source.to_ascii_uppercase().as_str() {
+            "MATHGUARDIAN" | "MATH_GUARDIAN" => Some(SignalSource::MathGuardian),
+            "NEURALCORTEX" | "NEURAL_CORTEX" => Some(SignalSource::NeuralCortex),
+            "SYMBOLICCONSENSUS" | "SYMBOLIC_CONSENSUS" => Some(SignalSource::SymbolicConsensus),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SignalSource::MathGuardian => "MathGuardian",
+            SignalSource::NeuralCortex => "NeuralCortex",
+            SignalSource::SymbolicConsensus => "SymbolicConsensus",
+        }
+    }
+}
+
+#[napi(object)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct Position {
     pub symbol: String,
+    /// Signed quantity: positive long, negative short, 0.0 flat
     pub quantity: f64,
+    /// Weighted-average entry price of the open quantity
     pub entry_price: f64,
     pub current_price: f64,
+    /// Unrealised PnL at `current_price`
     pub pnl: f64,
+    /// PnL locked in by partial/full closes
+    pub realized_pnl: f64,
+    /// Total trading fees paid (maker + taker)
+    pub fees: f64,
+    pub maker_fees: f64,
+    pub taker_fees: f64,
+    /// Cumulative funding: positive received, negative paid
+    pub funding: f64,
+    /// Strategy of the latest attributed fill
+    pub strategy_id: Option<String>,
+    /// `proof_hash` of the latest attributed fill's TradingSignal
+    pub signal_hash: Option<String>,
+}
+
+impl Position {
+    /// Mark-to-market PnL. Quantity is signed: positive long, negative short.
+    pub fn unrealized_pnl(&self) -> f64 {
+        (self.current_price - self.entry_price) * self.quantity
+    }
+
+    pub fn notional(&self) -> f64 {
+        self.quantity * self.current_price
+    }
+
+    pub fn is_flat(&self) -> bool {
+        self.quantity.abs() < QTY_EPSILON
+    }
+
+    /// A `side` trade of `size` only shrinks the position (no open and no flip).
+    pub fn is_reduced_by(&self, side: Side, size: f64) -> bool {
+        let opposite = match side {
+            Side::Buy => self.quantity < 0.0,
+            Side::Sell => self.quantity > 0.0,
+        };
+        opposite && !self.is_flat() && size <= self.quantity.abs() + QTY_EPSILON
+    }
+
+    /// Realised + unrealised, before fees and funding.
+    pub fn gross_pnl(&self) -> f64 {
+        self.realized_pnl + self.pnl
+    }
+
+    /// Realised PnL - fees + funding: what the position has settled to the wallet.
+    pub fn booked_pnl(&self) -> f64 {
+        self.realized_pnl - self.fees + self.funding
+    }
+
+    pub fn net_pnl(&self) -> f64 {
+        self.gross_pnl() - self.fees + self.funding
+    }
+
+    /// Apply an execution: extends, reduces, closes or flips the position.
+    pub fn apply_fill(&mut self, side: Side, quantity: f64, price: f64, fee: f64, liquidity: Liquidity) {
+        let fill_qty = side.sign() * quantity;
+        let same_direction = self.is_flat() || self.quantity.signum() == fill_qty.signum();
+
+        if same_direction {
+            // Extend: weighted-average cost basis
+            let new_qty = self.quantity + fill_qty;
+            self.entry_price =
+                (self.quantity.abs() * self.entry_price + quantity * price) / new_qty.abs();
+            self.quantity = new_qty;
+        } else {
+            // Reduce: realise PnL on the closed part
+            let closed = quantity.min(self.quantity.abs());
+            self.realized_pnl += closed * (price - self.entry_price) * self.quantity.signum();
+
+            let new_qty = self.quantity + fill_qty;
+            if new_qty.abs() < QTY_EPSILON {
+                self.quantity = 0.0;
+                self.entry_price = 0.0;
+            } else if new_qty.signum() != self.quantity.signum() {
+                // Flip: the remainder opens at the fill price
+                self.quantity = new_qty;
+                self.entry_price = price;
+            } else {
+                self.quantity = new_qty;
+            }
+        }
+
+        self.fees += fee;
+        match liquidity {
+            Liquidity::Maker => self.maker_fees += fee,
+            Liquidity::Taker => self.taker_fees += fee,
+        }
+        self.current_price = price;
+        self.pnl = self.unrealized_pnl();
+    }
+}
+
+/// Ledger entry for one execution. Positions are derived from these.
+#[napi(object)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Fill {
+    pub id: String,
+    pub symbol: String,
+    /// "BUY" or "SELL"
+    pub side: String,
+    pub quantity: f64,
+    pub price: f64,
+    pub fee: f64,
+    /// "MAKER" or "TAKER"
+    pub liquidity: String,
+    pub timestamp_ms: i64,
+    /// `proof_hash` of the TradingSignal that originated the order
+    pub signal_hash: Option<String>,
+    pub strategy_id: Option<String>,
+    /// "MathGuardian", "NeuralCortex" or "SymbolicConsensus"
+    pub signal_source: Option<String>,
+}
+
+/// Periodic perpetual funding booked against a symbol.
+#[napi(object)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct FundingPayment {
+    pub id: String,
+    pub symbol: String,
+    pub rate: f64,
+    /// Positive received, negative paid
+    pub amount: f64,
+    pub timestamp_ms: i64,
+}
+
+/// Gross/fees/funding/net decomposition for one symbol or the whole book.
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct PnlBreakdown {
+    /// Symbol, or "TOTAL" for the aggregate
+    pub symbol: String,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub gross_pnl: f64,
+    pub maker_fees: f64,
+    pub taker_fees: f64,
+    pub fees: f64,
+    pub funding: f64,
+    pub net_pnl: f64,
+}
+
+impl PnlBreakdown {
+    fn add(&mut self, p: &Position) {
+        self.realized_pnl += p.realized_pnl;
+        self.unrealized_pnl += p.pnl;
+        self.gross_pnl += p.gross_pnl();
+        self.maker_fees += p.maker_fees;
+        self.taker_fees += p.taker_fees;
+        self.fees += p.fees;
+        self.funding += p.funding;
+        self.net_pnl += p.net_pnl();
+    }
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct PnlReport {
+    pub positions: Vec<PnlBreakdown>,
+    pub total: PnlBreakdown,
+}
+
+/// An execution to book. `id`/`timestamp_ms` are assigned when absent.
+#[derive(Debug, Clone)]
+pub struct FillInput {
+    pub id: Option<String>,
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: f64,
+    pub price: f64,
+    pub fee: f64,
+    pub liquidity: Liquidity,
+    pub timestamp_ms: Option<i64>,
+    pub signal_hash: Option<String>,
+    pub strategy_id: Option<String>,
+    pub signal_source: Option<SignalSource>,
+}
+
+impl FillInput {
+    pub fn new(symbol: &str, side: Side, quantity: f64, price: f64, fee: f64) -> Self {
+        FillInput {
+            id: None,
+            symbol: symbol.to_string(),
+            side,
+            quantity,
+            price,
+            fee,
+            liquidity: Liquidity::default(),
+            timestamp_ms: None,
+            signal_hash: None,
+            strategy_id: None,
+            signal_source: None,
+        }
+    }
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct FillFilter {
+    pub symbol: Option<String>,
+    pub side: Option<String>,
+    pub since_ms: Option<i64>,
+    pub until_ms: Option<i64>,
+    pub signal_hash: Option<String>,
+    pub strategy_id: Option<String>,
+}
+
+impl FillFilter {
+    fn matches(&self, fill: &Fill) -> bool {
+        self.symbol.as_ref().is_none_or(|s| *s == fill.symbol)
+            && self.side.as_ref().is_none_or(|s| s.eq_ignore_ascii_case(&fill.side))
+            && self.since_ms.is_none_or(|t| fill.timestamp_ms >= t)
+            && self.until_ms.is_none_or(|t| fill.timestamp_ms <= t)
+            && self.signal_hash.as_ref().is_none_or(|h| fill.signal_hash.as_ref() == Some(h))
+            && self.strategy_id.as_ref().is_none_or(|id| fill.strategy_id.as_ref() == Some(id))
+    }
+}
+
+/// Result of replaying the fill ledger against the stored positions.
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct LedgerCheck {
+    /// Positions rebuilt purely from the ledger
+    pub positions: Vec<Position>,
+    /// Symbols whose stored position disagrees with the replay
+    pub mismatched_symbols: Vec<String>,
+    pub matches: bool,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct PriceUpdate {
+    pub symbol: String,
+    pub price: f64,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct PortfolioTotals {
+    /// Non-flat positions
+    pub position_count: u32,
+    /// Sum of absolute notionals at current prices
+    pub gross_exposure: f64,
+    /// Long notional minus short notional at current prices
+    pub net_exposure: f64,
+    pub unrealized_pnl: f64,
+    pub realized_pnl: f64,
+    pub fees: f64,
+    pub funding: f64,
+    /// Realised + unrealised - fees + funding
+    pub net_pnl: f64,
+}
+
+impl PortfolioTotals {
+    pub fn from_positions(positions: &[Position]) -> Self {
+        positions.iter().fold(PortfolioTotals::default(), |mut totals, p| {
+            if !p.is_flat() {
+                totals.position_count += 1;
+            }
+            totals.gross_exposure += p.notional().abs();
+            totals.net_exposure += p.notional();
+            totals.unrealized_pnl += p.pnl;
+            totals.realized_pnl += p.realized_pnl;
+            totals.fees += p.fees;
+            totals.funding += p.funding;
+            totals.net_pnl += p.net_pnl();
+            totals
+        })
+    }
+}
+
+/// Typed view of the whole portfolio at one version.
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct PortfolioSnapshot {
+    pub positions: Vec<Position>,
+    pub totals: PortfolioTotals,
+    /// Unix ms of the latest recorded commit (None if nothing committed yet)
+    pub last_update_ms: Option<i64>,
+    /// Hex-encoded version the snapshot was read at
+    pub version: String,
+}
+
+/// Writes full Loro snapshots to disk, atomically (tmp file + rename).
+struct Checkpointer {
+    path: PathBuf,
+    // None disables periodic checkpoints (on-demand only)
+    interval: Option<Duration>,
+    last_saved: Instant,
+    // Version on disk; the doc has unsaved changes while its oplog is ahead of it
+    saved: VersionVector,
+    // A failed background save, reported by the next commit
+    failure: Option<GuardianError>,
+}
+
+impl Checkpointer {
+    fn is_dirty(&self, doc: &LoroDoc) -> bool {
+        doc.oplog_vv() != self.saved
+    }
+
+    fn is_due(&self, doc: &LoroDoc) -> bool {
+        self.interval
+            .is_some_and(|interval| self.last_saved.elapsed() >= interval)
+            && self.is_dirty(doc)
+    }
+
+    fn save(&mut self, doc: &LoroDoc) -> Result<()> {
+        let bytes = doc
+            .export(ExportMode::Snapshot)
+            .map_err(|e| GuardianError::Serialization(format!("Failed to export portfolio snapshot: {}", e)))?;
+
+        let tmp = self.path.with_extension("tmp");
+        let io_error = |action: &str, path: &PathBuf, e: std::io::Error| {
+            GuardianError::Serialization(format!("Failed to {} {}: {}", action, path.display(), e))
+        };
+        fs::write(&tmp, &bytes).map_err(|e| io_error("write", &tmp, e))?;
+        fs::File::open(&tmp)
+            .and_then(|f| f.sync_all())
+            .map_err(|e| io_error("sync", &tmp, e))?;
+        fs::rename(&tmp, &self.path).map_err(|e| io_error("replace", &self.path, e))?;
+        // The rename only survives a crash once the directory entry is synced
+        #[cfg(unix)]
+        {
+            let dir = match self.path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+                _ => PathBuf::from("."),
+            };
+            fs::File::open(&dir)
+                .and_then(|d| d.sync_all())
+                .map_err(|e| io_error("sync", &dir, e))?;
+        }
+
+        self.last_saved = Instant::now();
+        self.saved = doc.oplog_vv();
+        self.failure = None;
+        Ok(())
+    }
+}
+
+/// Saves changes left after the last commit, so a quiet portfolio still
+/// reaches disk within one interval. Stops (and joins) on drop.
+struct FlushTimer {
+    stop: Option<mpsc::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl FlushTimer {
+    fn spawn(doc: Arc<Mutex<LoroDoc>>, checkpointer: Arc<Mutex<Checkpointer>>, interval: Duration) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                // A poisoned lock fails every other call too; nothing left to save
+                let (Ok(doc), Ok(mut checkpointer)) = (doc.lock(), checkpointer.lock()) else {
+                    return;
+                };
+                if checkpointer.is_dirty(&doc) {
+                    if let Err(e) = checkpointer.save(&doc) {
+                        checkpointer.failure = Some(e);
+                    }
+                }
+            }
+        });
+        Self {
+            stop: Some(stop),
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for FlushTimer {
+    fn drop(&mut self) {
+        // Disconnecting the channel wakes the timer and ends its loop
+        self.stop.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Version of the portfolio document right after a local commit.
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct CommitRecord {
+    pub timestamp_ms: i64,
+    /// Hex-encoded Loro frontiers, usable with `get_portfolio_at_version`
+    pub version: String,
 }
 
 pub struct PortfolioState {
     doc: Arc<Mutex<LoroDoc>>,
+    checkpointer: Option<Arc<Mutex<Checkpointer>>>,
+    changes: Arc<Mutex<ChangeFeed>>,
+    // Keeps the Loro event subscription feeding `changes` alive
+    _subscription: Subscription,
+    _flush_timer: Option<FlushTimer>,
 }
 
 impl PortfolioState {
     pub fn new() -> Self {
-        let doc = LoroDoc::new();
+        Self::from_doc(LoroDoc::new(), None)
+    }
+
+    fn from_doc(doc: LoroDoc, checkpointer: Option<Checkpointer>) -> Self {
+        let changes = Arc::new(Mutex::new(ChangeFeed::default()));
+        let feed = Arc::clone(&changes);
+        // Pending changes stay usable after a poisoning panic; they are only queued here
+        let subscription = doc.subscribe_root(Arc::new(move |event| {
+            feed.lock().unwrap_or_else(PoisonError::into_inner).record(&event)
+        }));
+
+        let doc = Arc::new(Mutex::new(doc));
+        let interval = checkpointer.as_ref().and_then(|c| c.interval);
+        let checkpointer = checkpointer.map(|c| Arc::new(Mutex::new(c)));
+        let flush_timer = checkpointer
+            .as_ref()
+            .zip(interval)
+            .map(|(c, interval)| FlushTimer::spawn(Arc::clone(&doc), Arc::clone(c), interval));
+
         PortfolioState {
-            doc: Arc::new(Mutex::new(doc)),
+            doc,
+            checkpointer,
+            changes,
+            _subscription: subscription,
+            _flush_timer: flush_timer,
         }
     }
 
-    pub fn update_position(&self, symbol: String, quantity: f64, price: f64) {
-        let doc = self.doc.lock().unwrap();
+    /// Restore from the snapshot at `path` (or start empty if it does not exist)
+    /// and checkpoint back to it every `interval_ms` while there are unsaved
+    /// changes (0 = on-demand only). Refuses to start from a snapshot that
+    /// cannot be decoded.
+    pub fn open(path: impl Into<PathBuf>, interval_ms: u32) -> Result<Self> {
+        let path = path.into();
+
+        let doc = if path.exists() {
+            let bytes = fs::read(&path)
+                .map_err(|e| GuardianError::Serialization(format!("Failed to read {}: {}", path.display(), e)))?;
+            let doc = LoroDoc::from_snapshot(&bytes).map_err(|e| {
+                GuardianError::Serialization(format!("Portfolio snapshot {} is unreadable: {}", path.display(), e))
+            })?;
+            Self::verify(&doc).map_err(|e| {
+                GuardianError::Serialization(format!("Portfolio snapshot {} is corrupt: {}", path.display(), e.message()))
+            })?;
+            Self::index_fills(&doc)?;
+            doc
+        } else {
+            LoroDoc::new()
+        };
+
+        let checkpointer = Checkpointer {
+            path,
+            interval: (interval_ms > 0).then(|| Duration::from_millis(interval_ms as u64)),
+            last_saved: Instant::now(),
+            saved: doc.oplog_vv(),
+            failure: None,
+        };
+        Ok(Self::from_doc(doc, Some(checkpointer)))
+    }
+
+    /// Write a snapshot to the configured path now.
+    pub fn checkpoint(&self) -> Result<()> {
+        let Some(checkpointer) = &self.checkpointer else {
+            return Err(GuardianError::State("No snapshot path configured for this portfolio".to_string()));
+        };
+        let doc = self.lock()?;
+        error::lock(checkpointer, "Checkpoint")?.save(&doc)
+    }
+
+    /// Move the position to a target `quantity` (signed) at `price`.
+    /// The difference is booked as a fee-less fill, so cost basis is kept.
+    pub fn update_position(&self, symbol: String, quantity: f64, price: f64) -> Result<Position> {
+        if !quantity.is_finite() {
+            return Err(GuardianError::Validation("Position quantity must be finite".to_string()));
+        }
+        validate_price(price)?;
+        let doc = self.lock()?;
+        let map = doc.get_map("positions");
+
+        let mut position = Self::read_position(&map, &symbol).unwrap_or_else(|| Position {
+            symbol: symbol.clone(),
+            ..Position::default()
+        });
+
+        let delta = quantity - position.quantity;
+        if delta.abs() >= QTY_EPSILON {
+            let side = if delta > 0.0 { Side::Buy } else { Side::Sell };
+            let fill = Self::append_fill(&doc, FillInput::new(&symbol, side, delta.abs(), price, 0.0))?;
+            position.apply_fill(side, fill.quantity, fill.price, fill.fee, Liquidity::Taker);
+        } else {
+            position.current_price = price;
+            position.pnl = position.unrealized_pnl();
+        }
+
+        Self::write_position(&map, &position)?;
+        self.commit(&doc)?;
+        Ok(position)
+    }
+
+    /// Append the fill to the ledger and derive the new position from it,
+    /// in one commit. A fill whose `id` is already in the ledger is ignored.
+    pub fn record_fill(&self, input: FillInput) -> Result<Position> {
+        let doc = self.lock()?;
+        match Self::book_fill(&doc, input.clone())? {
+            Some(position) => {
+                self.commit(&doc)?;
+                Ok(position)
+            }
+            None => Ok(Self::read_position(&doc.get_map("positions"), &input.symbol).unwrap_or_else(|| Position {
+                symbol: input.symbol,
+                ..Position::default()
+            })),
+        }
+    }
+
+    /// Start tracking an order in state NEW.
+    pub fn submit_order(&self, input: &OrderInput) -> Result<Order> {
+        let side = Side::parse(&input.side)
+            .ok_or_else(|| GuardianError::Validation(format!("Invalid side: {}", input.side)))?;
+        if input.id.is_empty() {
+            return Err(GuardianError::Validation("Order id must not be empty".to_string()));
+        }
+        if !(input.quantity.is_finite() && input.quantity > 0.0) {
+            return Err(GuardianError::Validation("Order quantity must be positive".to_string()));
+        }
+        if !input.price.is_none_or(|p| p.is_finite() && p > 0.0) {
+            return Err(GuardianError::Validation("Limit price must be positive".to_string()));
+        }
+        let signal_source = match &input.signal_source {
+            Some(s) => Some(
+                SignalSource::parse(s)
+                    .ok_or_else(|| GuardianError::Validation(format!("Invalid signal source: {}", s)))?,
+            ),
+            None => None,
+        };
+
+        let doc = self.lock()?;
+        let map = doc.get_map("orders");
+        if Self::read_order(&map, &input.id).is_some() {
+            return Err(GuardianError::State(format!("Order {} already exists", input.id)));
+        }
+
+        let now = now_ms();
+        let order = Order {
+            id: input.id.clone(),
+            symbol: input.symbol.clone(),
+            side: side.as_str().to_string(),
+            quantity: input.quantity,
+            price: input.price,
+            status: OrderStatus::New.as_str().to_string(),
+            strategy_id: input.strategy_id.clone(),
+            signal_hash: input.signal_hash.clone(),
+            signal_source: signal_source.map(|s| s.as_str().to_string()),
+            created_ms: now,
+            updated_ms: now,
+            ..Order::default()
+        };
+        Self::write_order(&map, &order)?;
+        self.commit(&doc)?;
+        Ok(order)
+    }
+
+    /// Move an order to ACKNOWLEDGED, CANCELLED or REJECTED.
+    pub fn transition_order(&self, id: &str, next: OrderStatus, reason: Option<String>) -> Result<Order> {
+        let doc = self.lock()?;
+        let map = doc.get_map("orders");
+        let mut order = Self::read_order(&map, id).ok_or_else(|| unknown_order(id))?;
+        if order.status().is_late_ack(next) {
+            return Ok(order);
+        }
+        if !order.status().can_become(next) {
+            return Err(GuardianError::State(format!(
+                "Order {} cannot move from {} to {}",
+                id,
+                order.status,
+                next.as_str()
+            )));
+        }
+
+        order.status = next.as_str().to_string();
+        order.reason = reason.or(order.reason);
+        order.updated_ms = now_ms();
+        Self::write_order(&map, &order)?;
+        self.commit(&doc)?;
+        Ok(order)
+    }
+
+    /// Book an execution against an order: the fill goes to the ledger and
+    /// the position, and the order becomes PARTIALLY_FILLED or FILLED, all in
+    /// one commit. A `fill_id` already in the ledger leaves the order as is.
+    pub fn fill_order(
+        &self,
+        id: &str,
+        quantity: f64,
+        price: f64,
+        fee: f64,
+        liquidity: Liquidity,
+        fill_id: Option<String>,
+    ) -> Result<Order> {
+        let doc = self.lock()?;
+        let map = doc.get_map("orders");
+        let mut order = Self::read_order(&map, id).ok_or_else(|| unknown_order(id))?;
+        // A replayed execution is acknowledged before the quantity and status
+        // checks, which it fails once it has filled the order
+        if fill_id.as_deref().is_some_and(|fill_id| Self::is_booked(&doc, fill_id)) {
+            return Ok(order);
+        }
+
+        if quantity > order.remaining() * (1.0 + 1e-9) + QTY_EPSILON {
+            return Err(GuardianError::Validation(format!(
+                "Fill of {} exceeds the {} remaining on order {}",
+                quantity,
+                order.remaining(),
+                id
+            )));
+        }
+        let filled = order.filled_quantity + quantity;
+        let next = if filled >= order.quantity - QTY_EPSILON {
+            OrderStatus::Filled
+        } else if order.status() == OrderStatus::Cancelled {
+            // A late partial execution is booked, but the order no longer rests
+            OrderStatus::Cancelled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+        if next != order.status() && !order.status().can_become(next) {
+            return Err(GuardianError::State(format!("Order {} is {} and cannot be filled", id, order.status)));
+        }
+
+        let side = Side::parse(&order.side)
+            .ok_or_else(|| GuardianError::Serialization(format!("Order {} has an invalid side", id)))?;
+        let input = FillInput {
+            id: fill_id,
+            liquidity,
+            strategy_id: order.strategy_id.clone(),
+            signal_hash: order.signal_hash.clone(),
+            signal_source: order.signal_source.as_deref().and_then(SignalSource::parse),
+            ..FillInput::new(&order.symbol, side, quantity, price, fee)
+        };
+        if Self::book_fill(&doc, input)?.is_none() {
+            return Ok(order);
+        }
+
+        order.average_fill_price = (order.average_fill_price * order.filled_quantity + price * quantity) / filled;
+        order.filled_quantity = filled;
+        order.status = next.as_str().to_string();
+        order.updated_ms = now_ms();
+        Self::write_order(&map, &order)?;
+        self.commit(&doc)?;
+        Ok(order)
+    }
+
+    pub fn order(&self, id: &str) -> Result<Option<Order>> {
+        Ok(Self::read_order(&self.lock()?.get_map("orders"), id))
+    }
+
+    /// Tracked orders (only NEW/ACKNOWLEDGED/PARTIALLY_FILLED when `open_only`),
+    /// oldest first.
+    pub fn orders(&self, symbol: Option<&str>, open_only: bool) -> Result<Vec<Order>> {
+        let doc = self.lock()?;
+        let map = doc.get_map("orders");
+        let mut orders: Vec<Order> = map
+            .keys()
+            .filter_map(|id| Self::read_order(&map, &id))
+            .filter(|o| symbol.is_none_or(|s| s == o.symbol))
+            .filter(|o| !open_only || o.status().is_open())
+            .collect();
+        orders.sort_by_key(|o| o.created_ms);
+        Ok(orders)
+    }
+
+    /// Open-order count and resting limit notional for `symbol`.
+    pub fn order_exposure(&self, symbol: &str) -> Result<OrderExposure> {
+        Ok(orders::exposure(&self.orders(Some(symbol), true)?))
+    }
+
+    /// Force each target symbol to its `quantity` @ `entry_price` (e.g. exchange
+    /// values), all in one commit. Each is booked as zero-fee ledger fills with
+    /// ids starting `id_prefix`: a close at the current entry (no realised PnL)
+    /// and a re-open at the target price, so the ledger still replays to the
+    /// adopted positions. Nothing is written if any target is invalid.
+    pub fn adopt_positions(&self, targets: &[ExchangePosition], id_prefix: &str) -> Result<Vec<Position>> {
+        for (i, target) in targets.iter().enumerate() {
+            if !target.quantity.is_finite() {
+                return Err(GuardianError::Validation(format!("Quantity for {} must be finite", target.symbol)));
+            }
+            if target.quantity.abs() >= QTY_EPSILON {
+                validate_price(target.entry_price)?;
+            }
+            if targets[..i].iter().any(|t| t.symbol == target.symbol) {
+                return Err(GuardianError::Validation(format!("{} is listed twice", target.symbol)));
+            }
+        }
+
+        let doc = self.lock()?;
+        let map = doc.get_map("positions");
+        // The ledger length keeps ids unique on this replica, the peer id across replicas
+        let base = format!("{}-{}-{}", id_prefix, doc.peer_id(), doc.get_list("fills").len());
+        let fill_id = |symbol: &str, leg: &str| format!("{}-{}-{}", base, symbol, leg);
+        let taken = targets
+            .iter()
+            .flat_map(|t| [fill_id(&t.symbol, "close"), fill_id(&t.symbol, "open")])
+            .find(|id| Self::is_booked(&doc, id));
+        if let Some(id) = taken {
+            return Err(GuardianError::State(format!("Fill {} is already in the ledger", id)));
+        }
+
+        let mut adopted = Vec::with_capacity(targets.len());
+        for target in targets {
+            let symbol = target.symbol.as_str();
+            let mut position = Self::read_position(&map, symbol).unwrap_or_else(|| Position {
+                symbol: symbol.to_string(),
+                ..Position::default()
+            });
+            let mark = position.current_price;
+
+            if !position.is_flat() {
+                let side = if position.quantity > 0.0 { Side::Sell } else { Side::Buy };
+                let close = FillInput {
+                    id: Some(fill_id(symbol, "close")),
+                    ..FillInput::new(symbol, side, position.quantity.abs(), position.entry_price, 0.0)
+                };
+                let fill = Self::append_fill(&doc, close)?;
+                position.apply_fill(side, fill.quantity, fill.price, fill.fee, Liquidity::Taker);
+            }
+            if target.quantity.abs() >= QTY_EPSILON {
+                let side = if target.quantity > 0.0 { Side::Buy } else { Side::Sell };
+                let open = FillInput {
+                    id: Some(fill_id(symbol, "open")),
+                    ..FillInput::new(symbol, side, target.quantity.abs(), target.entry_price, 0.0)
+                };
+                let fill = Self::append_fill(&doc, open)?;
+                position.apply_fill(side, fill.quantity, fill.price, fill.fee, Liquidity::Taker);
+            }
+
+            // Keep the last mark rather than the synthetic fill price
+            if mark > 0.0 {
+                position.current_price = mark;
+                position.pnl = position.unrealized_pnl();
+            }
+
+            Self::write_position(&map, &position)?;
+            adopted.push(position);
+        }
+
+        self.commit(&doc)?;
+        Ok(adopted)
+    }
+
+    /// Ledger entries matching `filter`, in ledger order.
+    pub fn fills(&self, filter: &FillFilter) -> Result<Vec<Fill>> {
+        let doc = self.lock()?;
+        Ok(Self::read_fills(&doc)
+            .into_iter()
+            .filter(|f| filter.matches(f))
+            .collect())
+    }
+
+    /// Replay the whole ledger and compare with the stored positions.
+    pub fn rebuild_from_ledger(&self) -> Result<LedgerCheck> {
+        let doc = self.lock()?;
+        let map = doc.get_map("positions");
+        let mut rebuilt = Self::replay_ledger(&doc);
+        let mut mismatched_symbols = Vec::new();
+
+        for position in rebuilt.iter_mut() {
+            match Self::read_position(&map, &position.symbol) {
+                Some(stored) => {
+                    // Marks come from price updates, not fills
+                    position.current_price = stored.current_price;
+                    position.pnl = position.unrealized_pnl();
+                    if !Self::books_match(&stored, position) {
+                        mismatched_symbols.push(position.symbol.clone());
+                    }
+                }
+                None => mismatched_symbols.push(position.symbol.clone()),
+            }
+        }
+        for symbol in map.keys() {
+            if !rebuilt.iter().any(|p| *p.symbol == *symbol) {
+                mismatched_symbols.push(symbol.to_string());
+            }
+        }
+
+        Ok(LedgerCheck {
+            matches: mismatched_symbols.is_empty(),
+            positions: rebuilt,
+            mismatched_symbols,
+        })
+    }
+
+    // Positions as the fill ledger and funding payments say they should be.
+    // Marks are not in the ledger, so `current_price` is the last fill price.
+    fn replay_ledger(doc: &LoroDoc) -> Vec<Position> {
+        let mut rebuilt: Vec<Position> = Vec::new();
+        for fill in Self::read_fills(doc) {
+            let Some(side) = Side::parse(&fill.side) else { continue };
+            let liquidity = Liquidity::parse(&fill.liquidity).unwrap_or_default();
+            let index = match rebuilt.iter().position(|p| p.symbol == fill.symbol) {
+                Some(i) => i,
+                None => {
+                    rebuilt.push(Position {
+                        symbol: fill.symbol.clone(),
+                        ..Position::default()
+                    });
+                    rebuilt.len() - 1
+                }
+            };
+            let position = &mut rebuilt[index];
+            position.apply_fill(side, fill.quantity, fill.price, fill.fee, liquidity);
+            if fill.strategy_id.is_some() || fill.signal_hash.is_some() {
+                position.strategy_id = fill.strategy_id;
+                position.signal_hash = fill.signal_hash;
+            }
+        }
+        for payment in Self::read_funding(doc) {
+            match rebuilt.iter_mut().find(|p| p.symbol == payment.symbol) {
+                Some(position) => position.funding += payment.amount,
+                None => rebuilt.push(Position {
+                    symbol: payment.symbol.clone(),
+                    funding: payment.amount,
+                    ..Position::default()
+                }),
+            }
+        }
+        rebuilt
+    }
+
+    fn books_match(stored: &Position, derived: &Position) -> bool {
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0);
+        close(stored.quantity, derived.quantity)
+            && close(stored.entry_price, derived.entry_price)
+            && close(stored.realized_pnl, derived.realized_pnl)
+            && close(stored.fees, derived.fees)
+            && close(stored.maker_fees, derived.maker_fees)
+            && close(stored.funding, derived.funding)
+    }
+
+    // Concurrent fills on one symbol each rewrite that position's fields, and
+    // the merge keeps only one replica's write per field. The merged ledger has
+    // every fill, so positions are re-derived from it (keeping merged marks).
+    fn rederive_positions(doc: &LoroDoc) -> Result<()> {
+        let map = doc.get_map("positions");
+        for mut position in Self::replay_ledger(doc) {
+            if let Some(stored) = Self::read_position(&map, &position.symbol) {
+                position.current_price = stored.current_price;
+                position.pnl = position.unrealized_pnl();
+                if Self::books_match(&stored, &position) {
+                    continue;
+                }
+            }
+            Self::write_position(&map, &position)?;
+        }
+        Ok(())
+    }
+
+    /// Revalue a position at `new_price`. Returns the updated position,
+    /// or None if the guardian holds nothing in `symbol`.
+    pub fn update_price(&self, symbol: &str, new_price: f64) -> Result<Option<Position>> {
+        validate_price(new_price)?;
+        let doc = self.lock()?;
+        let map = doc.get_map("positions");
+
+        let position = Self::mark_to_market(&map, symbol, new_price)?;
+        if position.is_some() {
+            self.commit_marks(&doc)?;
+        }
+        Ok(position)
+    }
+
+    /// Revalue several positions under a single commit.
+    /// Returns how many held positions were revalued.
+    /// Nothing is written if any price is invalid.
+    pub fn update_prices(&self, updates: &[PriceUpdate]) -> Result<u32> {
+        for update in updates {
+            validate_price(update.price)?;
+        }
+        let doc = self.lock()?;
+        let map = doc.get_map("positions");
+
+        let mut revalued = 0;
+        for update in updates {
+            if Self::mark_to_market(&map, &update.symbol, update.price)?.is_some() {
+                revalued += 1;
+            }
+        }
+        if revalued > 0 {
+            self.commit_marks(&doc)?;
+        }
+        Ok(revalued)
+    }
+
+    pub fn position(&self, symbol: &str) -> Result<Option<Position>> {
+        let doc = self.lock()?;
+        Ok(Self::read_position(&doc.get_map("positions"), symbol))
+    }
+
+    pub fn positions(&self) -> Result<Vec<Position>> {
+        let doc = self.lock()?;
+        Ok(Self::read_positions(&doc))
+    }
+
+    pub fn totals(&self) -> Result<PortfolioTotals> {
+        Ok(PortfolioTotals::from_positions(&self.positions()?))
+    }
+
+    /// Positions, totals and last update time read under one lock.
+    pub fn snapshot(&self) -> Result<PortfolioSnapshot> {
+        let doc = self.lock()?;
+        let positions = Self::read_positions(&doc);
+        Ok(PortfolioSnapshot {
+            totals: PortfolioTotals::from_positions(&positions),
+            positions,
+            last_update_ms: match doc.get_map("meta").get("last_update_ms") {
+                Some(ValueOrContainer::Value(LoroValue::I64(ts))) => Some(ts),
+                _ => None,
+            },
+            version: encode_hex(&doc.oplog_frontiers().encode()),
+        })
+    }
+
+    /// Gross PnL, fees (maker/taker), funding and net PnL per symbol and in aggregate.
+    pub fn pnl_breakdown(&self) -> Result<PnlReport> {
+        let mut report = PnlReport {
+            total: PnlBreakdown {
+                symbol: "TOTAL".to_string(),
+                ..PnlBreakdown::default()
+            },
+            ..PnlReport::default()
+        };
+        for position in self.positions()? {
+            let mut line = PnlBreakdown {
+                symbol: position.symbol.clone(),
+                ..PnlBreakdown::default()
+            };
+            line.add(&position);
+            report.total.add(&position);
+            report.positions.push(line);
+        }
+        Ok(report)
+    }
+
+    /// PnL by strategy, signal source and symbol, valued at current marks.
+    pub fn attribution(&self) -> Result<AttributionReport> {
+        let doc = self.lock()?;
+        let map = doc.get_map("positions");
+        Ok(attribution::attribute(&Self::read_fills(&doc), |symbol| {
+            Self::read_position(&map, symbol).map(|p| p.current_price)
+        }))
+    }
+
+    /// Book a funding payment. Without an explicit `amount` it is derived from
+    /// the position: longs pay `rate * notional` when the rate is positive.
+    pub fn apply_funding(&self, symbol: &str, rate: f64, amount: Option<f64>) -> Result<FundingPayment> {
+        if !(rate.is_finite() && amount.is_none_or(f64::is_finite)) {
+            return Err(GuardianError::Validation("Funding rate and amount must be finite".to_string()));
+        }
+        let doc = self.lock()?;
         let map = doc.get_map("positions");
-        
-        let position_data = serde_json::json!({
-            "symbol": symbol,
-            "quantity": quantity,
-            "entry_price": price,
-            "current_price": price, // Init current as entry
-            "pnl": 0.0
+
+        let mut position = Self::read_position(&map, symbol).unwrap_or_else(|| Position {
+            symbol: symbol.to_string(),
+            ..Position::default()
         });
 
-        // Insert or update logic handled by Loro's CRDT nature
-        map.insert(&symbol, position_data).unwrap();
+        let ledger = doc.get_list("funding");
+        let payment = FundingPayment {
+            id: format!("{}-{}", doc.peer_id(), ledger.len()),
+            symbol: symbol.to_string(),
+            rate,
+            amount: amount.unwrap_or(-rate * position.notional()),
+            timestamp_ms: now_ms(),
+        };
+
+        let entry = ledger.push_container(LoroMap::new())?;
+        entry.insert("id", payment.id.as_str())?;
+        entry.insert("symbol", payment.symbol.as_str())?;
+        entry.insert("rate", payment.rate)?;
+        entry.insert("amount", payment.amount)?;
+        entry.insert("timestamp_ms", payment.timestamp_ms)?;
+
+        position.funding += payment.amount;
+        Self::write_position(&map, &position)?;
+        self.commit(&doc)?;
+        Ok(payment)
+    }
+
+    /// Funding payments booked for `symbol` (all symbols if None).
+    pub fn funding_payments(&self, symbol: Option<&str>) -> Result<Vec<FundingPayment>> {
+        let doc = self.lock()?;
+        Ok(Self::read_funding(&doc)
+            .into_iter()
+            .filter(|p| symbol.is_none_or(|s| s == p.symbol))
+            .collect())
+    }
+
+    /// Update wallet balance, maintenance rate and margin limits in one commit.
+    /// A new wallet balance is taken to include everything booked so far.
+    pub fn configure_account(&self, config: &AccountConfig) -> Result<AccountSettings> {
+        config.validate()?;
+        let doc = self.lock()?;
+        let account = doc.get_map("account");
+
+        let mut settings = Self::read_account(&doc);
+        config.apply(&mut settings);
+        if config.wallet_balance.is_some() {
+            settings.booked_pnl_at_balance = Self::read_positions(&doc).iter().map(Position::booked_pnl).sum();
+        }
+        account.insert("wallet_balance", settings.wallet_balance)?;
+        account.insert("booked_pnl_at_balance", settings.booked_pnl_at_balance)?;
+        account
+            .insert("maintenance_margin_rate", settings.maintenance_margin_rate)?;
+        account.insert("max_margin_usage", settings.max_margin_usage)?;
+        account
+            .insert("min_liquidation_distance", settings.min_liquidation_distance)?;
+
+        self.commit(&doc)?;
+        Ok(settings)
+    }
+
+    pub fn set_leverage(&self, symbol: &str, leverage: f64) -> Result<()> {
+        if !(leverage.is_finite() && leverage >= 1.0) {
+            return Err(GuardianError::Validation(format!("Leverage must be at least 1, got {}", leverage)));
+        }
+        let doc = self.lock()?;
+        doc.get_map("leverage").insert(symbol, leverage)?;
+        self.commit(&doc)?;
+        Ok(())
+    }
+
+    /// Equity, used/free margin and per-position liquidation estimates.
+    pub fn account_state(&self) -> Result<AccountState> {
+        let doc = self.lock()?;
+        let leverage = doc.get_map("leverage");
+        Ok(margin::account_state(&Self::read_account(&doc), &Self::read_positions(&doc), |s| {
+            Self::read_leverage(&leverage, s)
+        }))
+    }
+
+    /// Margin usage and liquidation distance if `size` of `symbol` traded at
+    /// `price`, plus the configured limits they are checked against.
+    pub fn project_margin(
+        &self,
+        symbol: &str,
+        side: Side,
+        size: f64,
+        price: f64,
+    ) -> Result<(MarginProjection, AccountSettings)> {
+        let doc = self.lock()?;
+        let leverage = doc.get_map("leverage");
+        let settings = Self::read_account(&doc);
+        let projection = margin::project(
+            &settings,
+            &Self::read_positions(&doc),
+            |s| Self::read_leverage(&leverage, s),
+            symbol,
+            side,
+            size,
+            price,
+        );
+        Ok((projection, settings))
+    }
+
+    /// Recorded equity points (wallet balance + PnL settled since + unrealised
+    /// PnL, as in `account_state`), oldest first.
+    pub fn equity_curve(&self) -> Result<Vec<EquityPoint>> {
+        let doc = self.lock()?;
+        let mut curve: Vec<EquityPoint> = Self::read_list(&doc, "equity");
+        curve.sort_by_key(|p| p.timestamp_ms);
+        Ok(curve)
+    }
+
+    /// Encoded version vector of everything this replica has seen.
+    pub fn version(&self) -> Result<Vec<u8>> {
+        Ok(self.lock()?.oplog_vv().encode())
+    }
+
+    /// Updates another replica is missing, given its encoded version vector
+    /// (all history when `since` is None).
+    pub fn export_updates(&self, since: Option<&[u8]>) -> Result<Vec<u8>> {
+        let doc = self.lock()?;
+        let from = match since {
+            Some(bytes) => VersionVector::decode(bytes)
+                .map_err(|e| GuardianError::Validation(format!("Invalid version vector: {}", e)))?,
+            None => VersionVector::default(),
+        };
+        doc.export(ExportMode::updates(&from))
+            .map_err(|e| GuardianError::Serialization(format!("Failed to export updates: {}", e)))
+    }
+
+    /// Merge updates exported by another replica. Concurrent edits to the same
+    /// field resolve last-writer-wins (Lamport clock, then peer id), so every
+    /// replica converges on the same values regardless of import order.
+    /// Positions are re-derived from the merged fill ledger whenever it grew.
+    /// Returns false if some updates wait on history not yet received.
+    pub fn import_updates(&self, bytes: &[u8]) -> Result<bool> {
+        let doc = self.lock()?;
+        let ledger_len = |doc: &LoroDoc| (doc.get_list("fills").len(), doc.get_list("funding").len());
+        let before = ledger_len(&doc);
+        let status = doc
+            .import(bytes)
+            .map_err(|e| GuardianError::Serialization(format!("Failed to import updates: {}", e)))?;
+        if ledger_len(&doc) != before {
+            Self::rederive_positions(&doc)?;
+        }
+        self.commit(&doc)?;
+        Ok(status.pending.is_none())
+    }
+
+    /// Hex-encoded frontiers of the current state.
+    pub fn current_version(&self) -> Result<String> {
+        Ok(encode_hex(&self.lock()?.oplog_frontiers().encode()))
+    }
+
+    /// Every recorded commit (local and merged from replicas), oldest first.
+    /// Price marks are not recorded.
+    pub fn commit_history(&self) -> Result<Vec<CommitRecord>> {
+        let doc = self.lock()?;
+        let mut history = Self::read_commits(&doc);
+        // Stable: commits within the same millisecond keep document order
+        history.sort_by_key(|c| c.timestamp_ms);
+        Ok(history)
+    }
+
+    /// Positions as they were at `version` (from `commit_history`/`current_version`).
+    pub fn positions_at_version(&self, version: &str) -> Result<Vec<Position>> {
+        let bytes = decode_hex(version)
+            .ok_or_else(|| GuardianError::Validation("Version must be a hex string".to_string()))?;
+        let frontiers = Frontiers::decode(&bytes)
+            .map_err(|e| GuardianError::Validation(format!("Invalid version: {}", e)))?;
+
+        let doc = self.lock()?;
+        doc.checkout(&frontiers)
+            .map_err(|e| GuardianError::State(format!("Version not found in portfolio history: {}", e)))?;
+        let map = doc.get_map("positions");
+        let positions = map
+            .keys()
+            .filter_map(|symbol| Self::read_position(&map, &symbol))
+            .collect();
+        doc.checkout_to_latest();
+        Ok(positions)
+    }
+
+    /// Positions as the guardian believed them at `timestamp_ms`
+    /// (empty if nothing had been committed yet).
+    pub fn positions_at_timestamp(&self, timestamp_ms: i64) -> Result<Vec<Position>> {
+        match self
+            .commit_history()?
+            .into_iter()
+            .rev()
+            .find(|c| c.timestamp_ms <= timestamp_ms)
+        {
+            Some(commit) => self.positions_at_version(&commit.version),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Raw Loro value of the positions map, for debugging.
+    pub fn get_state_json(&self) -> Result<String> {
+        let doc = self.lock()?;
+        let map = doc.get_map("positions");
+        Ok(serde_json::to_string(&map.get_deep_value())?)
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, LoroDoc>> {
+        error::lock(&self.doc, "Portfolio")
+    }
+
+    // Commit a change to the books (fills, orders, funding, account), then
+    // record the resulting version for time-travel. The record lands in its
+    // own commit so it is not part of the version it describes.
+    fn commit(&self, doc: &LoroDoc) -> Result<()> {
+        self.commit_with_history(doc, true)
+    }
+
+    // Commit price marks. They move no books, so they are not recorded in the
+    // commit history; otherwise it would grow with every tick.
+    fn commit_marks(&self, doc: &LoroDoc) -> Result<()> {
+        self.commit_with_history(doc, false)
+    }
+
+    fn commit_with_history(&self, doc: &LoroDoc, books_changed: bool) -> Result<()> {
+        let changed = doc.get_pending_txn_len() > 0;
         doc.commit();
+
+        if changed {
+            let timestamp_ms = now_ms();
+            if books_changed {
+                let record = doc
+                    .get_list("commits")
+                    .push_container(LoroMap::new())?;
+                record.insert("timestamp_ms", timestamp_ms)?;
+                record.insert("version", doc.oplog_frontiers().encode())?;
+            }
+            doc.get_map("meta").insert("last_update_ms", timestamp_ms)?;
+            Self::record_equity(doc, timestamp_ms, !books_changed)?;
+            doc.commit();
+        }
+
+        self.publish_changes(doc)?;
+        self.maybe_checkpoint(doc)
+    }
+
+    // Append a point to the equity curve when equity moved since the last one.
+    // Points from marks alone carry `sample_start_ms` and are updated in place
+    // for EQUITY_SAMPLE_MS, so the curve grows with trading and time, not ticks.
+    fn record_equity(doc: &LoroDoc, timestamp_ms: i64, from_marks: bool) -> Result<()> {
+        let equity = margin::equity(&Self::read_account(doc), &Self::read_positions(doc));
+        let curve = doc.get_list("equity");
+        let last = match curve.len().checked_sub(1).and_then(|i| curve.get(i)) {
+            Some(ValueOrContainer::Container(Container::Map(point))) => Some(point),
+            _ => None,
+        };
+        let field = |point: &LoroMap, key: &str| match point.get(key) {
+            Some(ValueOrContainer::Value(value)) => Some(value),
+            _ => None,
+        };
+
+        if let Some(point) = &last {
+            if matches!(field(point, "equity"), Some(LoroValue::Double(v)) if v == equity) {
+                return Ok(());
+            }
+            let in_sample = matches!(
+                field(point, "sample_start_ms"),
+                Some(LoroValue::I64(start)) if timestamp_ms - start < EQUITY_SAMPLE_MS
+            );
+            if from_marks && in_sample {
+                point.insert("timestamp_ms", timestamp_ms)?;
+                point.insert("equity", equity)?;
+                return Ok(());
+            }
+        }
+
+        let point = curve.push_container(LoroMap::new())?;
+        point.insert("timestamp_ms", timestamp_ms)?;
+        point.insert("equity", equity)?;
+        if from_marks {
+            point.insert("sample_start_ms", timestamp_ms)?;
+        }
+        Ok(())
     }
 
-    pub fn update_price(&self, symbol: String, new_price: f64) {
-        let doc = self.doc.lock().unwrap();
+    /// Register a listener for position changes; returns its id.
+    pub fn subscribe(&self, listener: Listener) -> Result<u32> {
+        Ok(error::lock(&self.changes, "Change feed")?.subscribe(listener))
+    }
+
+    pub fn unsubscribe(&self, id: u32) -> Result<bool> {
+        Ok(error::lock(&self.changes, "Change feed")?.unsubscribe(id))
+    }
+
+    // Loro events only say what changed; read the settled positions once the
+    // commit/import is done and hand them to listeners.
+    fn publish_changes(&self, doc: &LoroDoc) -> Result<()> {
+        let map = doc.get_map("positions");
+        error::lock(&self.changes, "Change feed")?.flush(|symbol| Self::read_position(&map, symbol));
+        Ok(())
+    }
+
+    // A failed periodic save (here or on the flush timer) is returned even
+    // though the edit itself is applied; the changes stay unsaved and the
+    // next commit or timer tick retries them.
+    fn maybe_checkpoint(&self, doc: &LoroDoc) -> Result<()> {
+        let Some(checkpointer) = &self.checkpointer else {
+            return Ok(());
+        };
+        let mut checkpointer = error::lock(checkpointer, "Checkpoint")?;
+        let failed = |e: GuardianError| {
+            GuardianError::Serialization(format!("Periodic checkpoint failed: {}", e.message()))
+        };
+        if checkpointer.is_due(doc) {
+            checkpointer.save(doc).map_err(failed)?;
+        }
+        checkpointer.failure.take().map_or(Ok(()), |e| Err(failed(e)))
+    }
+
+    fn read_positions(doc: &LoroDoc) -> Vec<Position> {
+        let map = doc.get_map("positions");
+        map.keys()
+            .filter_map(|symbol| Self::read_position(&map, &symbol))
+            .collect()
+    }
+
+    fn read_account(doc: &LoroDoc) -> AccountSettings {
+        serde_json::to_value(doc.get_map("account").get_deep_value())
+            .ok()
+            .and_then(|json| serde_json::from_value(json).ok())
+            .unwrap_or_default()
+    }
+
+    fn read_leverage(map: &LoroMap, symbol: &str) -> f64 {
+        match map.get(symbol) {
+            Some(ValueOrContainer::Value(LoroValue::Double(leverage))) => leverage,
+            _ => margin::DEFAULT_LEVERAGE,
+        }
+    }
+
+    // Ledger append + position update, without committing.
+    // Returns None (and books nothing) when the fill id is already in the ledger.
+    fn book_fill(doc: &LoroDoc, input: FillInput) -> Result<Option<Position>> {
         let map = doc.get_map("positions");
+        if input.id.as_deref().is_some_and(|id| Self::is_booked(doc, id)) {
+            return Ok(None);
+        }
+
+        let mut position = Self::read_position(&map, &input.symbol).unwrap_or_else(|| Position {
+            symbol: input.symbol.clone(),
+            ..Position::default()
+        });
+        let (side, liquidity) = (input.side, input.liquidity);
+        let fill = Self::append_fill(doc, input)?;
+        position.apply_fill(side, fill.quantity, fill.price, fill.fee, liquidity);
+        if fill.strategy_id.is_some() || fill.signal_hash.is_some() {
+            position.strategy_id = fill.strategy_id;
+            position.signal_hash = fill.signal_hash;
+        }
+
+        Self::write_position(&map, &position)?;
+        Ok(Some(position))
+    }
+
+    fn read_order(map: &LoroMap, id: &str) -> Option<Order> {
+        let value = match map.get(id)? {
+            ValueOrContainer::Container(Container::Map(fields)) => fields.get_deep_value(),
+            _ => return None,
+        };
+        serde_json::to_value(&value)
+            .ok()
+            .and_then(|json| serde_json::from_value(json).ok())
+    }
+
+    fn write_order(map: &LoroMap, order: &Order) -> Result<()> {
+        let fields = map.get_or_create_container(&order.id, LoroMap::new())?;
+        fields.insert("id", order.id.as_str())?;
+        fields.insert("symbol", order.symbol.as_str())?;
+        fields.insert("side", order.side.as_str())?;
+        fields.insert("quantity", order.quantity)?;
+        if let Some(price) = order.price {
+            fields.insert("price", price)?;
+        }
+        fields.insert("filled_quantity", order.filled_quantity)?;
+        fields.insert("average_fill_price", order.average_fill_price)?;
+        fields.insert("status", order.status.as_str())?;
+        if let Some(reason) = &order.reason {
+            fields.insert("reason", reason.as_str())?;
+        }
+        if let Some(strategy) = &order.strategy_id {
+            fields.insert("strategy_id", strategy.as_str())?;
+        }
+        if let Some(hash) = &order.signal_hash {
+            fields.insert("signal_hash", hash.as_str())?;
+        }
+        if let Some(source) = &order.signal_source {
+            fields.insert("signal_source", source.as_str())?;
+        }
+        fields.insert("created_ms", order.created_ms)?;
+        fields.insert("updated_ms", order.updated_ms)?;
+        Ok(())
+    }
+
+    fn read_commits(doc: &LoroDoc) -> Vec<CommitRecord> {
+        let LoroValue::List(entries) = doc.get_list("commits").get_deep_value() else {
+            return Vec::new();
+        };
+        entries
+            .iter()
+            .filter_map(|entry| {
+                let LoroValue::Map(fields) = entry else { return None };
+                match (fields.get("timestamp_ms")?, fields.get("version")?) {
+                    (LoroValue::I64(ts), LoroValue::Binary(version)) => Some(CommitRecord {
+                        timestamp_ms: *ts,
+                        version: encode_hex(version),
+                    }),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    fn read_fills(doc: &LoroDoc) -> Vec<Fill> {
+        Self::read_list(doc, "fills")
+    }
+
+    fn read_funding(doc: &LoroDoc) -> Vec<FundingPayment> {
+        Self::read_list(doc, "funding")
+    }
+
+    fn read_list<T: serde::de::DeserializeOwned>(doc: &LoroDoc, name: &str) -> Vec<T> {
+        let LoroValue::List(entries) = doc.get_list(name).get_deep_value() else {
+            return Vec::new();
+        };
+        entries
+            .iter()
+            .filter_map(|entry| {
+                serde_json::to_value(entry)
+                    .ok()
+                    .and_then(|json| serde_json::from_value(json).ok())
+            })
+            .collect()
+    }
+
+    fn is_booked(doc: &LoroDoc, fill_id: &str) -> bool {
+        doc.get_map("fill_ids").get(fill_id).is_some()
+    }
+
+    // Documents written before the "fill_ids" index existed get it built once
+    fn index_fills(doc: &LoroDoc) -> Result<()> {
+        let index = doc.get_map("fill_ids");
+        if !index.is_empty() || doc.get_list("fills").is_empty() {
+            return Ok(());
+        }
+        for fill in Self::read_fills(doc) {
+            index.insert(&fill.id, true)?;
+        }
+        doc.commit();
+        Ok(())
+    }
+
+    // Ledger is append-only: entries are pushed, never edited or removed.
+    // Ids also go into the "fill_ids" map so duplicates are found without
+    // reading the ledger.
+    fn append_fill(doc: &LoroDoc, input: FillInput) -> Result<Fill> {
+        let ledger = doc.get_list("fills");
+        let fill = Fill {
+            id: input
+                .id
+                .unwrap_or_else(|| format!("{}-{}", doc.peer_id(), ledger.len())),
+            symbol: input.symbol,
+            side: input.side.as_str().to_string(),
+            quantity: input.quantity,
+            price: input.price,
+            fee: input.fee,
+            liquidity: input.liquidity.as_str().to_string(),
+            timestamp_ms: input.timestamp_ms.unwrap_or_else(now_ms),
+            signal_hash: input.signal_hash,
+            strategy_id: input.strategy_id,
+            signal_source: input.signal_source.map(|s| s.as_str().to_string()),
+        };
 
-        if let Some(value) = map.get(&symbol) {
-            // Loro values need handling, for MVP we overwrite with updated JSON
-            // In a full implementation we would edit the fields directly via sub-containers
-            // but simplified overwrite is safer for NAPI MVP.
-            // Note: This logic is simplified; real CRDT field editing is more complex.
+        let entry = ledger.push_container(LoroMap::new())?;
+        entry.insert("id", fill.id.as_str())?;
+        entry.insert("symbol", fill.symbol.as_str())?;
+        entry.insert("side", fill.side.as_str())?;
+        entry.insert("quantity", fill.quantity)?;
+        entry.insert("price", fill.price)?;
+        entry.insert("fee", fill.fee)?;
+        entry.insert("liquidity", fill.liquidity.as_str())?;
+        entry.insert("timestamp_ms", fill.timestamp_ms)?;
+        if let Some(hash) = &fill.signal_hash {
+            entry.insert("signal_hash", hash.as_str())?;
         }
+        if let Some(strategy) = &fill.strategy_id {
+            entry.insert("strategy_id", strategy.as_str())?;
+        }
+        if let Some(source) = &fill.signal_source {
+            entry.insert("signal_source", source.as_str())?;
+        }
+        doc.get_map("fill_ids").insert(&fill.id, true)?;
+        Ok(fill)
     }
 
-    pub fn get_state_json(&self) -> String {
-        let doc = self.doc.lock().unwrap();
+    // Every entry under "positions" must decode as a Position
+    fn verify(doc: &LoroDoc) -> Result<()> {
         let map = doc.get_map("positions");
-        let value = map.get_value();
-        serde_json::to_string(&value).unwrap_or_default()
+        for symbol in map.keys() {
+            if Self::read_position(&map, &symbol).is_none() {
+                return Err(GuardianError::Serialization(format!(
+                    "position entry '{}' cannot be decoded",
+                    symbol
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn read_position(map: &LoroMap, symbol: &str) -> Option<Position> {
+        let value = match map.get(symbol)? {
+            ValueOrContainer::Container(Container::Map(fields)) => fields.get_deep_value(),
+            _ => return None,
+        };
+        serde_json::to_value(&value)
+            .ok()
+            .and_then(|json| serde_json::from_value(json).ok())
+    }
+
+    // Each position is a nested map so replicas can merge field-level edits
+    fn write_position(map: &LoroMap, position: &Position) -> Result<()> {
+        let fields = map
+            .get_or_create_container(&position.symbol, LoroMap::new())?;
+        fields.insert("symbol", position.symbol.as_str())?;
+        fields.insert("quantity", position.quantity)?;
+        fields.insert("entry_price", position.entry_price)?;
+        fields.insert("current_price", position.current_price)?;
+        fields.insert("pnl", position.pnl)?;
+        fields.insert("realized_pnl", position.realized_pnl)?;
+        fields.insert("fees", position.fees)?;
+        fields.insert("maker_fees", position.maker_fees)?;
+        fields.insert("taker_fees", position.taker_fees)?;
+        fields.insert("funding", position.funding)?;
+        if let Some(strategy) = &position.strategy_id {
+            fields.insert("strategy_id", strategy.as_str())?;
+        }
+        if let Some(hash) = &position.signal_hash {
+            fields.insert("signal_hash", hash.as_str())?;
+        }
+        Ok(())
+    }
+
+    fn mark_to_market(map: &LoroMap, symbol: &str, price: f64) -> Result<Option<Position>> {
+        let Some(mut position) = Self::read_position(map, symbol) else {
+            return Ok(None);
+        };
+        position.current_price = price;
+        position.pnl = position.unrealized_pnl();
+
+        Self::write_position(map, &position)?;
+        Ok(Some(position))
+    }
+}
+
+fn validate_price(price: f64) -> Result<()> {
+    if price.is_finite() && price > 0.0 {
+        Ok(())
+    } else {
+        Err(GuardianError::Validation(format!("Price must be positive, got {}", price)))
+    }
+}
+
+fn unknown_order(id: &str) -> GuardianError {
+    GuardianError::State(format!("Unknown order: {}", id))
+}
+
+pub fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytics::{self, AnalyticsOptions};
+    use crate::events::PositionChange;
+
+    #[test]
+    fn test_mark_to_market_long_and_short() {
+        let state = PortfolioState::new();
+        state.update_position("BTC".to_string(), 2.0, 100.0).unwrap();
+        state.update_position("ETH".to_string(), -10.0, 50.0).unwrap();
+
+        let revalued = state.update_prices(&[
+            PriceUpdate { symbol: "BTC".to_string(), price: 110.0 },
+            PriceUpdate { symbol: "ETH".to_string(), price: 45.0 },
+            PriceUpdate { symbol: "SOL".to_string(), price: 20.0 },
+        ]).unwrap();
+        assert_eq!(revalued, 2);
+
+        let totals = state.totals().unwrap();
+        assert_eq!(totals.position_count, 2);
+        assert_eq!(totals.unrealized_pnl, 20.0 + 50.0);
+        assert_eq!(totals.gross_exposure, 220.0 + 450.0);
+        assert_eq!(totals.net_exposure, 220.0 - 450.0);
+
+        let btc = state.position("BTC").unwrap().unwrap();
+        let eth = state.position("ETH").unwrap().unwrap();
+        assert!(btc.is_reduced_by(Side::Sell, 2.0));
+        assert!(!btc.is_reduced_by(Side::Sell, 3.0)); // flips short
+        assert!(!btc.is_reduced_by(Side::Buy, 1.0));
+        assert!(eth.is_reduced_by(Side::Buy, 4.0));
+    }
+
+    #[test]
+    fn test_fill_averaging_partial_close_and_flip() {
+        let state = PortfolioState::new();
+
+        state.record_fill(FillInput::new("BTC", Side::Buy, 1.0, 100.0, 0.1)).unwrap();
+        let p = state.record_fill(FillInput::new("BTC", Side::Buy, 3.0, 200.0, 0.3)).unwrap();
+        assert_eq!(p.quantity, 4.0);
+        assert_eq!(p.entry_price, 175.0);
+
+        // Partial close keeps the cost basis
+        let p = state.record_fill(FillInput::new("BTC", Side::Sell, 1.0, 195.0, 0.1)).unwrap();
+        assert_eq!(p.quantity, 3.0);
+        assert_eq!(p.entry_price, 175.0);
+        assert_eq!(p.realized_pnl, 20.0);
+
+        // Flip: close 3 long, open 2 short at the fill price
+        let p = state.record_fill(FillInput::new("BTC", Side::Sell, 5.0, 165.0, 0.5)).unwrap();
+        assert_eq!(p.quantity, -2.0);
+        assert_eq!(p.entry_price, 165.0);
+        assert_eq!(p.realized_pnl, 20.0 - 30.0);
+        assert!((p.fees - 1.0).abs() < 1e-12);
+
+        // Full close of the short
+        let p = state.record_fill(FillInput::new("BTC", Side::Buy, 2.0, 160.0, 0.0)).unwrap();
+        assert!(p.is_flat());
+        assert_eq!(p.realized_pnl, -10.0 + 10.0);
+        assert_eq!(state.totals().unwrap().position_count, 0);
+    }
+
+    #[test]
+    fn test_replicas_converge_after_concurrent_edits() {
+        let a = PortfolioState::new();
+        let b = PortfolioState::new();
+        a.doc.lock().unwrap().set_peer_id(1).unwrap();
+        b.doc.lock().unwrap().set_peer_id(2).unwrap();
+
+        a.record_fill(FillInput::new("BTC", Side::Buy, 1.0, 100.0, 0.0)).unwrap();
+        b.import_updates(&a.export_updates(None).unwrap()).unwrap();
+
+        // Concurrent: both revalue BTC, each opens a different symbol
+        a.update_price("BTC", 110.0).unwrap();
+        a.record_fill(FillInput::new("ETH", Side::Sell, 2.0, 50.0, 0.0)).unwrap();
+        b.update_price("BTC", 105.0).unwrap();
+        b.record_fill(FillInput::new("SOL", Side::Buy, 10.0, 20.0, 0.0)).unwrap();
+
+        let a_version = a.version().unwrap();
+        let b_version = b.version().unwrap();
+        assert!(b.import_updates(&a.export_updates(Some(&b_version)).unwrap()).unwrap());
+        assert!(a.import_updates(&b.export_updates(Some(&a_version)).unwrap()).unwrap());
+
+        assert_eq!(a.get_state_json().unwrap(), b.get_state_json().unwrap());
+        assert_eq!(a.positions().unwrap().len(), 3);
+        // Same-field conflict resolves identically on both sides
+        assert_eq!(a.position("BTC").unwrap().unwrap().current_price, b.position("BTC").unwrap().unwrap().current_price);
+    }
+
+    #[test]
+    fn test_concurrent_fills_on_one_symbol_keep_both() {
+        let a = PortfolioState::new();
+        let b = PortfolioState::new();
+        a.doc.lock().unwrap().set_peer_id(1).unwrap();
+        b.doc.lock().unwrap().set_peer_id(2).unwrap();
+
+        let replayed = FillInput {
+            id: Some("weex-7".to_string()),
+            ..FillInput::new("BTC", Side::Buy, 1.0, 100.0, 0.1)
+        };
+        a.record_fill(replayed.clone()).unwrap();
+        b.record_fill(FillInput::new("BTC", Side::Buy, 2.0, 130.0, 0.2)).unwrap();
+
+        let a_version = a.version().unwrap();
+        let b_version = b.version().unwrap();
+        b.import_updates(&a.export_updates(Some(&b_version)).unwrap()).unwrap();
+        a.import_updates(&b.export_updates(Some(&a_version)).unwrap()).unwrap();
+
+        for replica in [&a, &b] {
+            let p = replica.position("BTC").unwrap().unwrap();
+            assert_eq!(p.quantity, 3.0);
+            assert!((p.entry_price - 120.0).abs() < 1e-9);
+            assert!((p.fees - 0.3).abs() < 1e-12);
+            assert!(replica.rebuild_from_ledger().unwrap().matches);
+        }
+        assert_eq!(a.get_state_json().unwrap(), b.get_state_json().unwrap());
+        // The fill id index merges along with the ledger
+        assert_eq!(b.record_fill(replayed).unwrap().quantity, 3.0);
+    }
+
+    #[test]
+    fn test_snapshot_includes_totals_and_update_time() {
+        let state = PortfolioState::new();
+        assert!(state.snapshot().unwrap().last_update_ms.is_none());
+
+        state.update_position("BTC".to_string(), 2.0, 100.0).unwrap();
+        state.update_price("BTC", 105.0).unwrap();
+
+        let snapshot = state.snapshot().unwrap();
+        assert_eq!(snapshot.positions.len(), 1);
+        assert_eq!(snapshot.totals.position_count, 1);
+        assert_eq!(snapshot.totals.unrealized_pnl, 10.0);
+        assert!(snapshot.last_update_ms.is_some());
+        assert_eq!(snapshot.version, state.current_version().unwrap());
+    }
+
+    #[test]
+    fn test_subscribers_receive_position_changes() {
+        let a = PortfolioState::new();
+        let b = PortfolioState::new();
+        let received: Arc<Mutex<Vec<PositionChange>>> = Arc::default();
+
+        let sink = Arc::clone(&received);
+        let id = b.subscribe(Box::new(move |changes| sink.lock().unwrap().extend_from_slice(changes))).unwrap();
+
+        b.update_position("BTC".to_string(), 1.0, 100.0).unwrap();
+        b.update_price("BTC", 101.0).unwrap();
+        a.update_position("ETH".to_string(), 2.0, 50.0).unwrap();
+        b.import_updates(&a.export_updates(None).unwrap()).unwrap();
+
+        let changes = received.lock().unwrap().clone();
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].source, "LOCAL");
+        assert!(changes[0].changed_fields.contains(&"quantity".to_string()));
+        assert_eq!(changes[1].changed_fields, vec!["current_price", "pnl"]);
+        assert_eq!(changes[1].position.as_ref().unwrap().pnl, 1.0);
+        assert_eq!((changes[2].symbol.as_str(), changes[2].source.as_str()), ("ETH", "IMPORT"));
+
+        // Time-travel reads are not changes
+        b.positions_at_version(&b.current_version().unwrap()).unwrap();
+        assert!(b.unsubscribe(id).unwrap());
+        b.update_price("BTC", 102.0).unwrap();
+        assert_eq!(received.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_equity_curve_follows_marks() {
+        let state = PortfolioState::new();
+        state
+            .configure_account(&AccountConfig {
+                wallet_balance: Some(1_000.0),
+                ..AccountConfig::default()
+            })
+            .unwrap();
+        state.update_position("BTC".to_string(), 1.0, 100.0).unwrap();
+        state.update_price("BTC", 90.0).unwrap();
+        // Unchanged equity is not recorded again
+        state.update_price("ETH", 50.0).unwrap();
+        state.set_leverage("BTC", 5.0).unwrap();
+        // A burst of marks updates one sampled point
+        for price in [95.0, 97.0, 96.0] {
+            state.update_price("BTC", price).unwrap();
+        }
+
+        let curve: Vec<f64> = state.equity_curve().unwrap().iter().map(|p| p.equity).collect();
+        assert_eq!(curve, vec![1_000.0, 996.0]);
+    }
+
+    #[test]
+    fn test_profitable_close_raises_the_equity_curve() {
+        // Default wallet balance of 0
+        let state = PortfolioState::new();
+        state.record_fill(FillInput::new("BTC", Side::Buy, 1.0, 100.0, 0.0)).unwrap();
+        state.record_fill(FillInput::new("BTC", Side::Sell, 1.0, 120.0, 0.0)).unwrap();
+
+        let curve: Vec<f64> = state.equity_curve().unwrap().iter().map(|p| p.equity).collect();
+        assert_eq!(curve, vec![0.0, 20.0]);
+        let report = analytics::performance(&state.equity_curve().unwrap(), &[], &AnalyticsOptions::default()).unwrap();
+        assert_eq!((report.drawdown, report.max_drawdown), (0.0, 0.0));
+        assert!(report.sharpe.is_finite());
+    }
+
+    #[test]
+    fn test_equity_includes_pnl_booked_since_the_balance() {
+        let state = PortfolioState::new();
+        state.record_fill(FillInput::new("ETH", Side::Buy, 1.0, 50.0, 0.5)).unwrap();
+        state
+            .configure_account(&AccountConfig {
+                wallet_balance: Some(1_000.0),
+                ..AccountConfig::default()
+            })
+            .unwrap();
+
+        // Profitable round trip plus funding after the balance snapshot
+        state.record_fill(FillInput::new("BTC", Side::Buy, 1.0, 100.0, 0.1)).unwrap();
+        state.apply_funding("BTC", 0.0, Some(-0.3)).unwrap();
+        state.record_fill(FillInput::new("BTC", Side::Sell, 1.0, 120.0, 0.1)).unwrap();
+
+        let account = state.account_state().unwrap();
+        assert!((account.settled_pnl - 19.5).abs() < 1e-9);
+        assert!((account.equity - 1_019.5).abs() < 1e-9);
+
+        // Re-syncing the balance from the exchange absorbs what was booked
+        state
+            .configure_account(&AccountConfig {
+                wallet_balance: Some(1_019.5),
+                ..AccountConfig::default()
+            })
+            .unwrap();
+        let account = state.account_state().unwrap();
+        assert_eq!(account.settled_pnl, 0.0);
+        assert_eq!(account.equity, 1_019.5);
+    }
+
+    #[test]
+    fn test_fills_tag_position_with_strategy() {
+        let state = PortfolioState::new();
+        state.record_fill(FillInput {
+            strategy_id: Some("momentum".to_string()),
+            signal_hash: Some("abc123".to_string()),
+            signal_source: Some(SignalSource::NeuralCortex),
+            ..FillInput::new("BTC", Side::Buy, 1.0, 100.0, 0.0)
+        }).unwrap();
+        // Untagged fills keep the last attribution
+        let p = state.record_fill(FillInput::new("BTC", Side::Buy, 1.0, 100.0, 0.0)).unwrap();
+        assert_eq!(p.strategy_id.as_deref(), Some("momentum"));
+        assert_eq!(p.signal_hash.as_deref(), Some("abc123"));
+
+        let filter = FillFilter {
+            strategy_id: Some("momentum".to_string()),
+            ..FillFilter::default()
+        };
+        let fills = state.fills(&filter).unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].signal_source.as_deref(), Some("NeuralCortex"));
+        assert!(state.rebuild_from_ledger().unwrap().matches);
+    }
+
+    #[test]
+    fn test_order_lifecycle_updates_position() {
+        let state = PortfolioState::new();
+        let input = OrderInput {
+            id: "o-1".to_string(),
+            symbol: "BTC".to_string(),
+            side: "BUY".to_string(),
+            quantity: 2.0,
+            price: Some(100.0),
+            strategy_id: Some("momentum".to_string()),
+            signal_hash: None,
+            signal_source: Some("neural_cortex".to_string()),
+        };
+        state.submit_order(&input).unwrap();
+        assert!(state.submit_order(&input).is_err());
+        state.transition_order("o-1", OrderStatus::Acknowledged, None).unwrap();
+
+        let order = state.fill_order("o-1", 0.5, 99.0, 0.0, Liquidity::Maker, Some("f-1".to_string())).unwrap();
+        assert_eq!(order.status, "PARTIALLY_FILLED");
+        // Replayed fill is ignored
+        let order = state.fill_order("o-1", 0.5, 99.0, 0.0, Liquidity::Maker, Some("f-1".to_string())).unwrap();
+        assert_eq!(order.filled_quantity, 0.5);
+
+        let exposure = state.order_exposure("BTC").unwrap();
+        assert_eq!(exposure.open_orders, 1);
+        assert_eq!(exposure.resting_notional, 150.0);
+
+        assert!(state.fill_order("o-1", 2.0, 100.0, 0.0, Liquidity::Maker, None).is_err());
+        let order = state.fill_order("o-1", 1.5, 101.0, 0.0, Liquidity::Maker, Some("f-2".to_string())).unwrap();
+        assert_eq!(order.status, "FILLED");
+        assert_eq!(order.average_fill_price, 100.5);
+        // Replaying the fill that completed the order is still a no-op
+        let order = state.fill_order("o-1", 1.5, 101.0, 0.0, Liquidity::Maker, Some("f-2".to_string())).unwrap();
+        assert_eq!((order.status.as_str(), order.filled_quantity), ("FILLED", 2.0));
+        assert!(state.transition_order("o-1", OrderStatus::Cancelled, None).is_err());
+
+        let position = state.position("BTC").unwrap().unwrap();
+        assert_eq!(position.quantity, 2.0);
+        assert_eq!(position.strategy_id.as_deref(), Some("momentum"));
+        assert_eq!(state.order_exposure("BTC").unwrap().open_orders, 0);
+
+        // Order fills are attributed to the order's signal source
+        let by_source = state.attribution().unwrap().by_source;
+        assert_eq!(by_source.len(), 1);
+        assert_eq!((by_source[0].key.as_str(), by_source[0].fill_count), ("NeuralCortex", 2));
+    }
+
+    #[test]
+    fn test_fills_racing_a_cancel_or_ack_are_booked() {
+        let state = PortfolioState::new();
+        let order = |id: &str| OrderInput {
+            id: id.to_string(),
+            symbol: "BTC".to_string(),
+            side: "BUY".to_string(),
+            quantity: 2.0,
+            price: Some(100.0),
+            strategy_id: None,
+            signal_hash: None,
+            signal_source: None,
+        };
+
+        // Cancel sent, but the exchange executed part and then the rest first
+        state.submit_order(&order("o-1")).unwrap();
+        state.transition_order("o-1", OrderStatus::Cancelled, None).unwrap();
+        let late = state.fill_order("o-1", 0.5, 100.0, 0.0, Liquidity::Maker, None).unwrap();
+        assert_eq!((late.status.as_str(), late.filled_quantity), ("CANCELLED", 0.5));
+        assert_eq!(state.order_exposure("BTC").unwrap().open_orders, 0);
+        let late = state.fill_order("o-1", 1.5, 100.0, 0.0, Liquidity::Maker, None).unwrap();
+        assert_eq!(late.status, "FILLED");
+
+        // Fill before the ack; the ack is then a no-op
+        state.submit_order(&order("o-2")).unwrap();
+        state.fill_order("o-2", 1.0, 100.0, 0.0, Liquidity::Maker, None).unwrap();
+        let acked = state.transition_order("o-2", OrderStatus::Acknowledged, None).unwrap();
+        assert_eq!(acked.status, "PARTIALLY_FILLED");
+
+        assert_eq!(state.position("BTC").unwrap().unwrap().quantity, 3.0);
+    }
+
+    #[test]
+    fn test_state_at_version_and_timestamp() {
+        let state = PortfolioState::new();
+        state.record_fill(FillInput::new("BTC", Side::Buy, 1.0, 100.0, 0.0)).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        state.record_fill(FillInput::new("BTC", Side::Buy, 1.0, 200.0, 0.0)).unwrap();
+        state.record_fill(FillInput::new("ETH", Side::Sell, 3.0, 50.0, 0.0)).unwrap();
+
+        let history = state.commit_history().unwrap();
+        assert_eq!(history.len(), 3);
+        let latest = state.positions_at_version(&state.current_version().unwrap()).unwrap();
+        assert_eq!(latest.len(), 2);
+
+        let first = state.positions_at_version(&history[0].version).unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].quantity, 1.0);
+        assert_eq!(first[0].entry_price, 100.0);
+
+        let at_first_ts = state.positions_at_timestamp(history[0].timestamp_ms).unwrap();
+        assert_eq!(at_first_ts[0].quantity, 1.0);
+        assert!(state.positions_at_timestamp(history[0].timestamp_ms - 1).unwrap().is_empty());
+
+        // Checkout is undone: live state and edits still work
+        assert_eq!(state.position("BTC").unwrap().unwrap().quantity, 2.0);
+        state.update_price("BTC", 160.0).unwrap();
+        state.record_fill(FillInput::new("BTC", Side::Sell, 1.0, 160.0, 0.0)).unwrap();
+        // Marks are not history
+        assert_eq!(state.commit_history().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip_and_corruption_check() {
+        let dir = std::env::temp_dir().join(format!("titan-guardian-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("portfolio.loro");
+        let _ = fs::remove_file(&path);
+
+        let state = PortfolioState::open(&path, 0).unwrap();
+        state.record_fill(FillInput::new("BTC", Side::Buy, 1.5, 100.0, 0.2)).unwrap();
+        state.checkpoint().unwrap();
+
+        let restored = PortfolioState::open(&path, 0).unwrap();
+        let p = restored.position("BTC").unwrap().unwrap();
+        assert_eq!(p.quantity, 1.5);
+        assert_eq!(p.entry_price, 100.0);
+
+        fs::write(&path, b"not a loro snapshot").unwrap();
+        assert!(PortfolioState::open(&path, 0).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_flush_timer_saves_trailing_changes() {
+        let dir = std::env::temp_dir().join(format!("titan-guardian-flush-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("portfolio.loro");
+        let _ = fs::remove_file(&path);
+
+        // The only commit happens before the interval is due
+        let state = PortfolioState::open(&path, 50).unwrap();
+        state.record_fill(FillInput::new("BTC", Side::Buy, 1.0, 100.0, 0.0)).unwrap();
+        assert!(!path.exists());
+
+        std::thread::sleep(Duration::from_millis(300));
+        drop(state);
+        let restored = PortfolioState::open(&path, 0).unwrap();
+        assert_eq!(restored.position("BTC").unwrap().unwrap().quantity, 1.0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_ledger_rebuild_matches_positions() {
+        let state = PortfolioState::new();
+        state.record_fill(FillInput::new("BTC", Side::Buy, 2.0, 100.0, 0.2)).unwrap();
+        state.record_fill(FillInput {
+            id: Some("weex-42".to_string()),
+            signal_hash: Some("0xabc".to_string()),
+            ..FillInput::new("BTC", Side::Sell, 1.0, 120.0, 0.1)
+        }).unwrap();
+        // Replayed exchange fill is ignored
+        state.record_fill(FillInput {
+            id: Some("weex-42".to_string()),
+            ..FillInput::new("BTC", Side::Sell, 1.0, 120.0, 0.1)
+        }).unwrap();
+        state.update_position("ETH".to_string(), -3.0, 50.0).unwrap();
+        state.update_price("BTC", 130.0).unwrap();
+
+        assert_eq!(state.fills(&FillFilter::default()).unwrap().len(), 3);
+        let tagged = state.fills(&FillFilter {
+            signal_hash: Some("0xabc".to_string()),
+            ..FillFilter::default()
+        }).unwrap();
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].id, "weex-42");
+
+        let check = state.rebuild_from_ledger().unwrap();
+        assert!(check.matches, "mismatches: {:?}", check.mismatched_symbols);
+        let btc = check.positions.iter().find(|p| p.symbol == "BTC").unwrap();
+        assert_eq!(btc.quantity, 1.0);
+        assert_eq!(btc.pnl, 30.0);
+
+        // Tampering with a derived position is detected
+        let doc = state.doc.lock().unwrap();
+        PortfolioState::write_position(&doc.get_map("positions"), &Position {
+            symbol: "ETH".to_string(),
+            quantity: -4.0,
+            ..Position::default()
+        })
+        .unwrap();
+        drop(doc);
+        assert_eq!(state.rebuild_from_ledger().unwrap().mismatched_symbols, vec!["ETH".to_string()]);
+    }
+
+    #[test]
+    fn test_fees_funding_and_net_pnl() {
+        let state = PortfolioState::new();
+        state.record_fill(FillInput {
+            liquidity: Liquidity::Maker,
+            ..FillInput::new("BTC", Side::Buy, 2.0, 100.0, 0.2)
+        }).unwrap();
+        state.record_fill(FillInput::new("BTC", Side::Sell, 1.0, 110.0, 0.5)).unwrap();
+        state.update_price("BTC", 120.0).unwrap();
+
+        // Long 1 @ 100 marked at 120, positive rate: longs pay 0.01% of notional
+        let payment = state.apply_funding("BTC", 0.0001, None).unwrap();
+        assert!((payment.amount + 0.012).abs() < 1e-12);
+        state.apply_funding("BTC", -0.0001, Some(0.5)).unwrap();
+
+        let report = state.pnl_breakdown().unwrap();
+        let btc = &report.positions[0];
+        assert_eq!(btc.realized_pnl, 10.0);
+        assert_eq!(btc.unrealized_pnl, 20.0);
+        assert_eq!(btc.gross_pnl, 30.0);
+        assert_eq!(btc.maker_fees, 0.2);
+        assert_eq!(btc.taker_fees, 0.5);
+        assert!((btc.funding - 0.488).abs() < 1e-12);
+        assert!((btc.net_pnl - (30.0 - 0.7 + 0.488)).abs() < 1e-12);
+        assert!((report.total.net_pnl - btc.net_pnl).abs() < 1e-12);
+
+        assert!(state.rebuild_from_ledger().unwrap().matches);
+    }
+
+    #[test]
+    fn test_bad_input_is_rejected_without_writing() {
+        let state = PortfolioState::new();
+        state.update_position("BTC".to_string(), 1.0, 100.0).unwrap();
+
+        let error = state.update_price("BTC", f64::NAN).unwrap_err();
+        assert_eq!(error.kind(), "VALIDATION");
+        let updates = [
+            PriceUpdate { symbol: "BTC".to_string(), price: 110.0 },
+            PriceUpdate { symbol: "ETH".to_string(), price: -1.0 },
+        ];
+        assert!(state.update_prices(&updates).is_err());
+        assert_eq!(state.position("BTC").unwrap().unwrap().current_price, 100.0);
+
+        let error = state.positions_at_version("not-hex").unwrap_err();
+        assert!(matches!(error, GuardianError::Validation(_)));
+        assert!(matches!(state.transition_order("nope", OrderStatus::Cancelled, None), Err(GuardianError::State(_))));
+    }
+
+    #[test]
+    fn test_update_position_keeps_cost_basis() {
+        let state = PortfolioState::new();
+        state.update_position("ETH".to_string(), 1.0, 100.0).unwrap();
+        let p = state.update_position("ETH".to_string(), 2.0, 200.0).unwrap();
+
+        assert_eq!(p.entry_price, 150.0);
+        assert_eq!(p.current_price, 200.0);
+        assert_eq!(p.pnl, 100.0);
     }
 }
@@ -0,0 +1,368 @@
+use napi_derive::napi;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Book levels scanned per side when looking for walls
+const WALL_SCAN_LEVELS: usize = 20;
+/// A level is a wall when its size is at least this multiple of the side's median level
+const WALL_SIZE_MULTIPLIER: f64 = 3.0;
+/// A wall shrinking below this fraction of its peak size counts as removed
+const WALL_RETAIN_RATIO: f64 = 0.5;
+/// Mid within this distance (bps) of a wall means price is "approaching" it
+const APPROACH_DISTANCE_BPS: f64 = 10.0;
+/// Walls younger than this are not yet trusted
+const MIN_PERSISTENCE_MS: f64 = 5_000.0;
+/// Levels considered for spoof-adjusted OFI (matches `calculate_ofi`)
+const OFI_DEPTH_LEVELS: usize = 5;
+/// Price grid used to match levels across snapshots when no tick size is given
+pub const DEFAULT_TICK_SIZE: f64 = 1e-8;
+/// Spoof scores fade linearly to zero this long after the last snapshot
+const SPOOF_SCORE_TTL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum BookSide {
+    Bid,
+    Ask,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct WallInfo {
+    pub price: f64,
+    pub size: f64,
+    /// Time the wall has been continuously resting (ms)
+    pub persistence_ms: f64,
+    /// Closest distance between mid and the wall observed so far (bps)
+    pub closest_approach_bps: f64,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct WallReport {
+    pub bid_walls: Vec<WallInfo>,
+    pub ask_walls: Vec<WallInfo>,
+    /// Likelihood (0.0 to 1.0) that bid-side walls are spoofed
+    pub bid_spoof_score: f64,
+    /// Likelihood (0.0 to 1.0) that ask-side walls are spoofed
+    pub ask_spoof_score: f64,
+    /// Top-5 OFI with wall volume discounted by its spoof score
+    pub adjusted_ofi: f64,
+    /// Walls pulled while price was approaching them (lifetime, both sides)
+    pub pulled_on_approach: u32,
+}
+
+#[derive(Debug, Clone)]
+struct TrackedWall {
+    side: BookSide,
+    // Price in ticks, so float noise between snapshots can't split a wall
+    tick: i64,
+    price: f64,
+    size: f64,
+    peak_size: f64,
+    first_seen_ms: i64,
+    last_seen_ms: i64,
+    /// Distance from mid when the wall was first seen
+    placed_distance_bps: f64,
+    closest_approach_bps: f64,
+}
+
+/// How walls on one side of the book have historically disappeared
+#[derive(Debug, Clone, Default)]
+struct SideHistory {
+    pulled_on_approach: u32,
+    cancelled_early: u32,
+    cancelled_late: u32,
+    consumed: u32,
+}
+
+impl SideHistory {
+    /// Fraction of resolved walls that behaved like spoofs, with a neutral
+    /// prior of one genuine wall so a single pull does not saturate the score.
+    fn pull_ratio(&self) -> f64 {
+        let suspicious = self.pulled_on_approach as f64 + 0.5 * self.cancelled_early as f64;
+        let resolved = (self.pulled_on_approach + self.cancelled_early + self.cancelled_late + self.consumed) as f64;
+        suspicious / (resolved + 1.0)
+    }
+}
+
+#[derive(Debug, Default)]
+struct SymbolWalls {
+    active: Vec<TrackedWall>,
+    history: HashMap<BookSide, SideHistory>,
+    last_scores: (f64, f64),
+    // When `last_scores` were computed (local clock; snapshot times are the caller's)
+    scored_at: Option<Instant>,
+}
+
+/// Tracks large resting levels across snapshots, per symbol.
+#[derive(Debug, Default)]
+pub struct WallTracker {
+    symbols: HashMap<String, SymbolWalls>,
+}
+
+impl WallTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `tick_size` must be positive; levels are matched on `round(price / tick_size)`.
+    pub fn update(
+        &mut self,
+        symbol: &str,
+        bids: &[(f64, f64)],
+        asks: &[(f64, f64)],
+        timestamp_ms: i64,
+        tick_size: f64,
+    ) -> WallReport {
+        let entry = self.symbols.entry(symbol.to_string()).or_default();
+        let to_tick = |price: f64| (price / tick_size).round() as i64;
+
+        let (Some(&(best_bid, _)), Some(&(best_ask, _))) = (bids.first(), asks.first()) else {
+            return entry.report(timestamp_ms, bids, asks, tick_size);
+        };
+        let mid = (best_bid + best_ask) / 2.0;
+
+        // 1. Resolve walls seen previously
+        let mut still_active = Vec::with_capacity(entry.active.len());
+        for mut wall in entry.active.drain(..) {
+            let levels = match wall.side {
+                BookSide::Bid => bids,
+                BookSide::Ask => asks,
+            };
+            let resting = levels
+                .iter()
+                .find(|(p, _v)| to_tick(*p) == wall.tick)
+                .map(|(_p, v)| *v)
+                .unwrap_or(0.0);
+
+            let distance_bps = (wall.price - mid).abs() / mid * 10_000.0;
+
+            if resting >= wall.peak_size * WALL_RETAIN_RATIO {
+                wall.size = resting;
+                wall.peak_size = wall.peak_size.max(resting);
+                wall.last_seen_ms = timestamp_ms;
+                wall.closest_approach_bps = wall.closest_approach_bps.min(distance_bps);
+                still_active.push(wall);
+                continue;
+            }
+
+            let traded_through = match wall.side {
+                BookSide::Bid => best_bid < wall.price,
+                BookSide::Ask => best_ask > wall.price,
+            };
+            let lifetime = (wall.last_seen_ms - wall.first_seen_ms) as f64;
+            let history = entry.history.entry(wall.side).or_default();
+            // Only an approach after placement counts: a wall placed near
+            // the touch and cancelled where it stood was never approached
+            let closest = distance_bps.min(wall.closest_approach_bps);
+            let approached = closest < wall.placed_distance_bps && closest <= APPROACH_DISTANCE_BPS;
+
+            if traded_through {
+                history.consumed += 1;
+            } else if approached {
+                history.pulled_on_approach += 1;
+            } else if lifetime < MIN_PERSISTENCE_MS {
+                history.cancelled_early += 1;
+            } else {
+                history.cancelled_late += 1;
+            }
+        }
+
+        // 2. Register new walls
+        for (side, levels) in [(BookSide::Bid, bids), (BookSide::Ask, asks)] {
+            for (price, size) in detect_walls(levels) {
+                let tick = to_tick(price);
+                if still_active.iter().any(|w| w.side == side && w.tick == tick) {
+                    continue;
+                }
+                let distance_bps = (price - mid).abs() / mid * 10_000.0;
+                still_active.push(TrackedWall {
+                    side,
+                    tick,
+                    price,
+                    size,
+                    peak_size: size,
+                    first_seen_ms: timestamp_ms,
+                    last_seen_ms: timestamp_ms,
+                    placed_distance_bps: distance_bps,
+                    closest_approach_bps: distance_bps,
+                });
+            }
+        }
+
+        entry.active = still_active;
+        entry.report(timestamp_ms, bids, asks, tick_size)
+    }
+
+    /// Latest (bid, ask) spoof scores for a symbol, fading to 0.0 once no
+    /// snapshot has arrived for `SPOOF_SCORE_TTL`; 0.0 if never tracked.
+    pub fn spoof_scores(&self, symbol: &str) -> (f64, f64) {
+        self.spoof_scores_at(symbol, Instant::now())
+    }
+
+    fn spoof_scores_at(&self, symbol: &str, now: Instant) -> (f64, f64) {
+        let Some((walls, scored_at)) = self.symbols.get(symbol).and_then(|s| Some((s, s.scored_at?))) else {
+            return (0.0, 0.0);
+        };
+        let age = now.saturating_duration_since(scored_at).as_secs_f64();
+        let weight = (1.0 - age / SPOOF_SCORE_TTL.as_secs_f64()).max(0.0);
+        (walls.last_scores.0 * weight, walls.last_scores.1 * weight)
+    }
+}
+
+impl SymbolWalls {
+    fn spoof_score(&self, side: BookSide, now_ms: i64) -> f64 {
+        let pull_ratio = self
+            .history
+            .get(&side)
+            .map(SideHistory::pull_ratio)
+            .unwrap_or(0.0);
+
+        // Youngest-first: a freshly placed wall has not earned trust yet
+        let youth = self
+            .active
+            .iter()
+            .filter(|w| w.side == side)
+            .map(|w| 1.0 - ((now_ms - w.first_seen_ms) as f64 / MIN_PERSISTENCE_MS).min(1.0))
+            .fold(0.0, f64::max);
+
+        (0.7 * pull_ratio + 0.3 * youth).clamp(0.0, 1.0)
+    }
+
+    fn report(&mut self, now_ms: i64, bids: &[(f64, f64)], asks: &[(f64, f64)], tick_size: f64) -> WallReport {
+        let bid_spoof_score = self.spoof_score(BookSide::Bid, now_ms);
+        let ask_spoof_score = self.spoof_score(BookSide::Ask, now_ms);
+        self.last_scores = (bid_spoof_score, ask_spoof_score);
+        self.scored_at = Some(Instant::now());
+
+        let walls_on = |side: BookSide| -> Vec<WallInfo> {
+            self.active
+                .iter()
+                .filter(|w| w.side == side)
+                .map(|w| WallInfo {
+                    price: w.price,
+                    size: w.size,
+                    persistence_ms: (now_ms - w.first_seen_ms) as f64,
+                    closest_approach_bps: w.closest_approach_bps,
+                })
+                .collect()
+        };
+
+        // Discount wall volume inside the OFI window by its spoof likelihood
+        let discounted_depth = |side: BookSide, levels: &[(f64, f64)], score: f64| -> f64 {
+            levels
+                .iter()
+                .take(OFI_DEPTH_LEVELS)
+                .map(|(p, v)| {
+                    let tick = (p / tick_size).round() as i64;
+                    let is_wall = self.active.iter().any(|w| w.side == side && w.tick == tick);
+                    if is_wall {
+                        v * (1.0 - score)
+                    } else {
+                        *v
+                    }
+                })
+                .sum()
+        };
+        let bid_depth = discounted_depth(BookSide::Bid, bids, bid_spoof_score);
+        let ask_depth = discounted_depth(BookSide::Ask, asks, ask_spoof_score);
+        let total = bid_depth + ask_depth;
+
+        WallReport {
+            bid_walls: walls_on(BookSide::Bid),
+            ask_walls: walls_on(BookSide::Ask),
+            bid_spoof_score,
+            ask_spoof_score,
+            adjusted_ofi: if total == 0.0 { 0.0 } else { (bid_depth - ask_depth) / total },
+            pulled_on_approach: self.history.values().map(|h| h.pulled_on_approach).sum(),
+        }
+    }
+}
+
+/// Levels whose size dwarfs the side's median level.
+fn detect_walls(levels: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let levels = &levels[..levels.len().min(WALL_SCAN_LEVELS)];
+    if levels.len() < 3 {
+        return Vec::new();
+    }
+
+    let mut sizes: Vec<f64> = levels.iter().map(|(_p, v)| *v).collect();
+    sizes.sort_by(|a, b| a.total_cmp(b));
+    let median = sizes[sizes.len() / 2];
+
+    levels
+        .iter()
+        .filter(|(_p, v)| *v >= median * WALL_SIZE_MULTIPLIER)
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Book = (Vec<(f64, f64)>, Vec<(f64, f64)>);
+
+    fn book_with_ask_wall(mid: f64, wall_price: f64, wall_size: f64) -> Book {
+        let bids: Vec<(f64, f64)> = (1..=5).map(|i| (mid - i as f64, 1.0)).collect();
+        let asks: Vec<(f64, f64)> = (1..=5)
+            .map(|i| mid + i as f64)
+            .map(|p| (p, if p == wall_price { wall_size } else { 1.0 }))
+            .collect();
+        (bids, asks)
+    }
+
+    #[test]
+    fn test_persistent_wall_is_trusted() {
+        let mut tracker = WallTracker::new();
+        let (bids, asks) = book_with_ask_wall(1000.0, 1003.0, 50.0);
+
+        tracker.update("ETH", &bids, &asks, 0, 0.01);
+        // The same level re-quoted with float noise is still the same wall
+        let asks: Vec<(f64, f64)> = asks.iter().map(|(p, v)| (p + 1e-9, *v)).collect();
+        let report = tracker.update("ETH", &bids, &asks, 10_000, 0.01);
+
+        assert_eq!(report.ask_walls.len(), 1);
+        assert_eq!(report.ask_walls[0].persistence_ms, 10_000.0);
+        assert_eq!(report.ask_spoof_score, 0.0);
+    }
+
+    #[test]
+    fn test_wall_pulled_on_approach_raises_score() {
+        let mut tracker = WallTracker::new();
+
+        for round in 0..3 {
+            let t = round * 2_000;
+            // Wall placed 30bps away, then mid rallies to within 10bps
+            let (bids, asks) = book_with_ask_wall(1000.0, 1003.0, 50.0);
+            tracker.update("ETH", &bids, &asks, t, 0.01);
+            let (bids, asks) = book_with_ask_wall(1002.0, 1003.0, 50.0);
+            tracker.update("ETH", &bids, &asks, t + 500, 0.01);
+            // Wall vanishes without trading through
+            let (bids, asks) = book_with_ask_wall(1002.0, 0.0, 0.0);
+            tracker.update("ETH", &bids, &asks, t + 1_000, 0.01);
+        }
+
+        let (bid_score, ask_score) = tracker.spoof_scores("ETH");
+        assert_eq!(bid_score, 0.0);
+        assert!(ask_score > 0.5, "repeated pulls should look like spoofing: {}", ask_score);
+
+        // Scores fade once snapshots stop arriving
+        let later = Instant::now() + SPOOF_SCORE_TTL / 2;
+        assert!(tracker.spoof_scores_at("ETH", later).1 < ask_score * 0.6);
+        assert_eq!(tracker.spoof_scores_at("ETH", later + SPOOF_SCORE_TTL), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_wall_cancelled_where_placed_is_not_an_approach_pull() {
+        let mut tracker = WallTracker::new();
+
+        // Placed 10bps from mid and pulled with mid unchanged
+        let (bids, asks) = book_with_ask_wall(1000.0, 1001.0, 50.0);
+        tracker.update("ETH", &bids, &asks, 0, 0.01);
+        let (bids, asks) = book_with_ask_wall(1000.0, 0.0, 0.0);
+        let report = tracker.update("ETH", &bids, &asks, 1_000, 0.01);
+
+        assert_eq!(report.pulled_on_approach, 0);
+    }
+}
@@ -0,0 +1,292 @@
+//! OHLCV bar aggregation from market ticks. Each series closes bars on its
+//! own clock (wall time, tick count, traded volume or traded notional) and
+//! keeps its own indicator trackers, fed with completed bars only.
+
+use crate::indicators::{read_tracker, BarIndicators, Dmi, IndicatorParams, IndicatorValues, WilderDmi, WilderRsi};
+use crate::MarketTick;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use wasm_bindgen::prelude::*;
+
+/// What closes a bar. The series `size` is in the matching unit.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BarType {
+    /// Fixed wall-clock buckets, `size` in milliseconds (60_000 = 1m)
+    Time,
+    /// Every `size` ticks
+    Tick,
+    /// Once `size` base units have traded
+    Volume,
+    /// Once `size` quote units (price * volume) have traded
+    Dollar,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bar {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Size traded over the bar
+    pub volume: f64,
+    /// Sum of price * traded size
+    pub notional: f64,
+    pub ticks: u32,
+    /// Bucket start for time bars, first tick otherwise
+    pub start_ms: u64,
+    /// Last tick in the bar
+    pub end_ms: u64,
+}
+
+impl Bar {
+    fn open(tick: &MarketTick, start_ms: u64) -> Self {
+        Self {
+            open: tick.price,
+            high: tick.price,
+            low: tick.price,
+            close: tick.price,
+            volume: 0.0,
+            notional: 0.0,
+            ticks: 0,
+            start_ms,
+            end_ms: tick.timestamp,
+        }
+    }
+
+    fn add(&mut self, tick: &MarketTick) {
+        let volume = tick.traded_volume;
+        self.high = self.high.max(tick.price);
+        self.low = self.low.min(tick.price);
+        self.close = tick.price;
+        self.volume += volume;
+        self.notional += tick.price * volume;
+        self.ticks += 1;
+        self.end_ms = tick.timestamp;
+    }
+}
+
+/// Folds ticks into the bar currently forming.
+#[derive(Debug, Clone)]
+pub struct BarBuilder {
+    kind: BarType,
+    size: f64,
+    current: Option<Bar>,
+    // Timestamp of the last tick taken, to reject ones from the past
+    last_ms: Option<u64>,
+}
+
+impl BarBuilder {
+    /// None unless `size` is positive and finite (and at least 1ms / 1 tick).
+    pub fn new(kind: BarType, size: f64) -> Option<Self> {
+        let minimum = match kind {
+            BarType::Time | BarType::Tick => 1.0,
+            BarType::Volume | BarType::Dollar => f64::MIN_POSITIVE,
+        };
+        (size.is_finite() && size >= minimum).then_some(Self {
+            kind,
+            size: if minimum == 1.0 { size.floor() } else { size },
+            current: None,
+            last_ms: None,
+        })
+    }
+
+    /// Add a tick, returning the bar it completed if any. Time bars close
+    /// when the first tick of a later bucket arrives, so quiet buckets
+    /// produce no bar rather than an empty one. Ticks older than the last
+    /// one taken are ignored.
+    pub fn push(&mut self, tick: &MarketTick) -> Option<Bar> {
+        if self.last_ms.is_some_and(|last| tick.timestamp < last) {
+            return None;
+        }
+        self.last_ms = Some(tick.timestamp);
+
+        let mut closed = None;
+        if self.kind == BarType::Time {
+            let bucket = self.bucket_start(tick.timestamp);
+            if self.current.as_ref().is_some_and(|bar| bar.start_ms != bucket) {
+                closed = self.current.take();
+            }
+        }
+
+        let start_ms = self.bucket_start(tick.timestamp);
+        let bar = self.current.get_or_insert_with(|| Bar::open(tick, start_ms));
+        bar.add(tick);
+
+        let full = match self.kind {
+            BarType::Time => false,
+            BarType::Tick => bar.ticks as f64 >= self.size,
+            BarType::Volume => bar.volume >= self.size,
+            BarType::Dollar => bar.notional >= self.size,
+        };
+        if full {
+            closed = self.current.take();
+        }
+        closed
+    }
+
+    fn bucket_start(&self, timestamp_ms: u64) -> u64 {
+        match self.kind {
+            BarType::Time => {
+                let size = self.size as u64;
+                timestamp_ms - timestamp_ms % size
+            }
+            _ => timestamp_ms,
+        }
+    }
+}
+
+/// One bar series: its builder, recent completed bars and the indicators
+/// computed on them.
+#[derive(Debug, Clone)]
+pub struct BarSeries {
+    builder: BarBuilder,
+    bars: VecDeque<Bar>,
+    capacity: usize,
+    rsi_trackers: Vec<WilderRsi>,
+    dmi_trackers: Vec<WilderDmi>,
+    indicators: BarIndicators,
+}
+
+impl BarSeries {
+    pub fn new(kind: BarType, size: f64, capacity: usize, params: &IndicatorParams) -> Option<Self> {
+        Some(Self {
+            builder: BarBuilder::new(kind, size)?,
+            bars: VecDeque::with_capacity(capacity),
+            capacity,
+            rsi_trackers: Vec::new(),
+            dmi_trackers: Vec::new(),
+            indicators: BarIndicators::new(params),
+        })
+    }
+
+    pub fn push(&mut self, tick: &MarketTick) {
+        let Some(bar) = self.builder.push(tick) else {
+            return;
+        };
+
+        for rsi in self.rsi_trackers.iter_mut() {
+            rsi.update(bar.close);
+        }
+        for dmi in self.dmi_trackers.iter_mut() {
+            dmi.update(bar.high, bar.low, bar.close);
+        }
+        self.indicators.update(bar.high, bar.low, bar.close);
+        self.bars.push_back(bar);
+        if self.bars.len() > self.capacity {
+            self.bars.pop_front();
+        }
+    }
+
+    /// Completed bars, oldest first
+    pub fn bars(&self) -> &VecDeque<Bar> {
+        &self.bars
+    }
+
+    pub fn indicators(&self) -> IndicatorValues {
+        self.indicators.values()
+    }
+
+    /// Wilder RSI of bar closes, None while warming up. Like the guardian's
+    /// tick RSI, a period first requested late is seeded from the held bars.
+    pub fn rsi(&mut self, period: usize) -> Option<f64> {
+        let bars = &self.bars;
+        let seed = || {
+            let mut rsi = WilderRsi::new(period);
+            for bar in bars.iter() {
+                rsi.update(bar.close);
+            }
+            rsi
+        };
+        read_tracker(&mut self.rsi_trackers, period, seed, WilderRsi::value)
+    }
+
+    pub fn dmi(&mut self, period: usize) -> Option<Dmi> {
+        let bars = &self.bars;
+        let seed = || {
+            let mut dmi = WilderDmi::new(period);
+            for bar in bars.iter() {
+                dmi.update(bar.high, bar.low, bar.close);
+            }
+            dmi
+        };
+        read_tracker(&mut self.dmi_trackers, period, seed, WilderDmi::value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(price: f64, volume: f64, timestamp: u64) -> MarketTick {
+        MarketTick {
+            price,
+            bid_volume: 10.0,
+            ask_volume: 10.0,
+            traded_volume: volume,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_time_bars_close_on_the_next_bucket() {
+        let mut builder = BarBuilder::new(BarType::Time, 60_000.0).unwrap();
+        assert_eq!(builder.push(&tick(100.0, 1.0, 60_500)), None);
+        assert_eq!(builder.push(&tick(103.0, 2.0, 70_000)), None);
+        assert_eq!(builder.push(&tick(99.0, 1.0, 119_999)), None);
+
+        // Skips the 120s bucket entirely; only one bar comes out
+        let bar = builder.push(&tick(101.0, 1.0, 185_000)).unwrap();
+        assert_eq!((bar.open, bar.high, bar.low, bar.close), (100.0, 103.0, 99.0, 99.0));
+        assert_eq!((bar.volume, bar.ticks), (4.0, 3));
+        assert_eq!((bar.start_ms, bar.end_ms), (60_000, 119_999));
+        assert_eq!(builder.current.as_ref().unwrap().start_ms, 180_000);
+    }
+
+    #[test]
+    fn test_ticks_from_the_past_are_ignored() {
+        let mut builder = BarBuilder::new(BarType::Time, 60_000.0).unwrap();
+        builder.push(&tick(100.0, 1.0, 125_000));
+        // Would otherwise close the forming bar and reopen an earlier bucket
+        assert_eq!(builder.push(&tick(90.0, 1.0, 65_000)), None);
+        builder.push(&tick(101.0, 1.0, 125_000));
+
+        let bar = builder.push(&tick(102.0, 1.0, 185_000)).unwrap();
+        assert_eq!((bar.start_ms, bar.low, bar.ticks), (120_000, 100.0, 2));
+    }
+
+    #[test]
+    fn test_activity_bars_close_at_their_threshold() {
+        let mut ticks = BarBuilder::new(BarType::Tick, 2.0).unwrap();
+        assert!(ticks.push(&tick(100.0, 1.0, 1)).is_none());
+        assert_eq!(ticks.push(&tick(101.0, 1.0, 2)).unwrap().ticks, 2);
+
+        let mut volume = BarBuilder::new(BarType::Volume, 5.0).unwrap();
+        assert!(volume.push(&tick(100.0, 3.0, 1)).is_none());
+        assert_eq!(volume.push(&tick(100.0, 3.0, 2)).unwrap().volume, 6.0);
+
+        let mut dollar = BarBuilder::new(BarType::Dollar, 1_000.0).unwrap();
+        assert!(dollar.push(&tick(100.0, 4.0, 1)).is_none());
+        let bar = dollar.push(&tick(200.0, 3.0, 2)).unwrap();
+        assert_eq!(bar.notional, 1_000.0);
+
+        assert!(BarBuilder::new(BarType::Time, 0.5).is_none());
+        assert!(BarBuilder::new(BarType::Dollar, f64::NAN).is_none());
+    }
+
+    #[test]
+    fn test_series_indicators_run_on_bar_closes() {
+        let mut series = BarSeries::new(BarType::Tick, 3.0, 100, &IndicatorParams::default()).unwrap();
+        // Three ticks per bar, closes rising by one per bar
+        for i in 0..30 {
+            let close = 100.0 + (i / 3) as f64;
+            series.push(&tick(close - 0.5 + (i % 3) as f64 * 0.25, 1.0, i));
+        }
+
+        assert_eq!(series.bars().len(), 10);
+        let mut reference = WilderRsi::new(5);
+        let expected = series.bars().iter().filter_map(|b| reference.update(b.close)).last();
+        assert_eq!(series.rsi(5), expected);
+        assert_eq!(series.rsi(50), None);
+    }
+}
@@ -0,0 +1,303 @@
+//! Tunable thresholds, score weights and windows for `SiliconMathGuardian`.
+//! Defaults reproduce the Golden Dataset constants in `lib.rs`; every signal
+//! echoes the config it was scored under.
+
+use crate::indicators::IndicatorParams;
+use serde::{Deserialize, Serialize};
+
+/// Upper bound on `buffer_size`: buffers are preallocated, and an
+/// allocation failure aborts the WASM instance
+pub const MAX_BUFFER_SIZE: usize = 100_000;
+/// Upper bound on `ofi_history_size`, for the same reason
+pub const MAX_OFI_HISTORY_SIZE: usize = 100_000;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ConfigError {
+    /// The config could not be read as a GuardianConfig at all
+    #[error("Malformed GuardianConfig: {0}")]
+    Malformed(String),
+    /// The config parsed but a value is out of range
+    #[error("Invalid GuardianConfig: {0}")]
+    Invalid(String),
+}
+
+/// Points each rule adds to (or removes from) the signal score.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoreWeights {
+    /// RSI below `rsi_collapsed` / above `rsi_sky_high`
+    pub rsi_extreme: f64,
+    /// RSI below `rsi_oversold` / above `rsi_overbought`
+    pub rsi_strong: f64,
+    /// RSI outside the buy / sell thresholds
+    pub rsi_mild: f64,
+    pub ofi_wall: f64,
+    pub failure_swing: f64,
+    pub bollinger: f64,
+    pub stochastic: f64,
+    pub trend_confirmation: f64,
+    pub macd_confirmation: f64,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self {
+            rsi_extreme: 4.0,
+            rsi_strong: 3.0,
+            rsi_mild: 1.0,
+            ofi_wall: 2.0,
+            failure_swing: 1.5,
+            bollinger: 1.5,
+            stochastic: 1.0,
+            trend_confirmation: 1.0,
+            macd_confirmation: 1.0,
+        }
+    }
+}
+
+/// Everything `generate_signal` decides with. Missing fields take their
+/// defaults, unknown fields are rejected.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GuardianConfig {
+    /// Ticks, prices and bars kept for replay and OFI
+    pub buffer_size: usize,
+    pub ofi_history_size: usize,
+    /// Ticks needed before OFI is reported
+    pub min_ticks_for_ofi: usize,
+    /// Prices needed before volatility is reported
+    pub min_prices_for_volatility: usize,
+
+    pub rsi_period: usize,
+    pub adx_period: usize,
+    pub rsi_collapsed: f64,
+    pub rsi_oversold: f64,
+    pub rsi_buy_threshold: f64,
+    pub rsi_sell_threshold: f64,
+    pub rsi_overbought: f64,
+    pub rsi_sky_high: f64,
+    /// RSI levels below / above which failure swings are looked for
+    pub failure_swing_oversold: f64,
+    pub failure_swing_overbought: f64,
+    pub stochastic_oversold: f64,
+    pub stochastic_overbought: f64,
+    pub ofi_significant_threshold: f64,
+    /// |trend strength| needed to confirm the score's direction
+    pub trend_strength_threshold: f64,
+
+    /// Return stdev (percent) above which every signal is HALT
+    pub volatility_halt_threshold: f64,
+    pub max_latency_ms: u64,
+
+    /// |score| needed for BUY / SELL
+    pub action_score_threshold: f64,
+    /// |score| mapped to confidence 1.0 (capped at 0.99)
+    pub confidence_score_scale: f64,
+    /// Below this for both math and neural, consensus holds
+    pub min_confidence_threshold: f64,
+    /// Neural confidence needed to act when math holds
+    pub neural_override_confidence: f64,
+    /// Added to the averaged confidence when math and neural agree
+    pub consensus_bonus: f64,
+    /// Math-only confidence needed for `can_execute`
+    pub standalone_execute_confidence: f64,
+
+    pub weights: ScoreWeights,
+    /// Lookbacks for the bar indicator set
+    pub indicators: IndicatorParams,
+}
+
+impl Default for GuardianConfig {
+    fn default() -> Self {
+        Self {
+            buffer_size: 100,
+            ofi_history_size: 50,
+            min_ticks_for_ofi: 10,
+            min_prices_for_volatility: 20,
+            rsi_period: crate::RSI_PERIOD,
+            adx_period: crate::ADX_PERIOD,
+            rsi_collapsed: crate::RSI_COLLAPSED,
+            rsi_oversold: 30.0,
+            rsi_buy_threshold: crate::RSI_BUY_THRESHOLD,
+            rsi_sell_threshold: crate::RSI_SELL_THRESHOLD,
+            rsi_overbought: 70.0,
+            rsi_sky_high: crate::RSI_SKY_HIGH,
+            failure_swing_oversold: 35.0,
+            failure_swing_overbought: 65.0,
+            stochastic_oversold: 20.0,
+            stochastic_overbought: 80.0,
+            ofi_significant_threshold: crate::OFI_SIGNIFICANT_THRESHOLD,
+            trend_strength_threshold: 0.5,
+            volatility_halt_threshold: crate::VOLATILITY_HALT_THRESHOLD,
+            max_latency_ms: crate::MAX_LATENCY_MS,
+            action_score_threshold: 4.0,
+            confidence_score_scale: 8.0,
+            min_confidence_threshold: crate::MIN_CONFIDENCE_THRESHOLD,
+            neural_override_confidence: 0.85,
+            consensus_bonus: 0.1,
+            standalone_execute_confidence: 0.7,
+            weights: ScoreWeights::default(),
+            indicators: IndicatorParams::default(),
+        }
+    }
+}
+
+impl GuardianConfig {
+    pub fn from_json(json: &str) -> Result<Self, ConfigError> {
+        let config: Self = serde_json::from_str(json).map_err(|e| ConfigError::Malformed(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |message: String| Err(ConfigError::Invalid(message));
+
+        // Momentum needs 10 prices and volatility at least one return
+        if !(10..=MAX_BUFFER_SIZE).contains(&self.buffer_size) {
+            return invalid(format!(
+                "buffer_size must be between 10 and {}, got {}",
+                MAX_BUFFER_SIZE, self.buffer_size
+            ));
+        }
+        if self.ofi_history_size > MAX_OFI_HISTORY_SIZE {
+            return invalid(format!(
+                "ofi_history_size must be at most {}, got {}",
+                MAX_OFI_HISTORY_SIZE, self.ofi_history_size
+            ));
+        }
+        if !(2..=self.buffer_size).contains(&self.min_prices_for_volatility) {
+            return invalid("min_prices_for_volatility must be between 2 and buffer_size".to_string());
+        }
+        if !(1..=self.buffer_size).contains(&self.min_ticks_for_ofi) {
+            return invalid("min_ticks_for_ofi must be between 1 and buffer_size".to_string());
+        }
+
+        let i = &self.indicators;
+        let periods = [
+            ("ofi_history_size", self.ofi_history_size),
+            ("rsi_period", self.rsi_period),
+            ("adx_period", self.adx_period),
+            ("indicators.sma_period", i.sma_period),
+            ("indicators.ema_period", i.ema_period),
+            ("indicators.macd_fast", i.macd_fast),
+            ("indicators.macd_slow", i.macd_slow),
+            ("indicators.macd_signal", i.macd_signal),
+            ("indicators.bollinger_period", i.bollinger_period),
+            ("indicators.atr_period", i.atr_period),
+            ("indicators.stochastic_k", i.stochastic_k),
+            ("indicators.stochastic_d", i.stochastic_d),
+        ];
+        if let Some((name, _)) = periods.iter().find(|(_, p)| *p == 0) {
+            return invalid(format!("{} must be at least 1", name));
+        }
+        if i.macd_fast >= i.macd_slow {
+            return invalid("indicators.macd_fast must be shorter than indicators.macd_slow".to_string());
+        }
+        if !(i.bollinger_width.is_finite() && i.bollinger_width > 0.0) {
+            return invalid("indicators.bollinger_width must be positive".to_string());
+        }
+
+        let rsi_levels = [
+            self.rsi_collapsed,
+            self.rsi_oversold,
+            self.rsi_buy_threshold,
+            self.rsi_sell_threshold,
+            self.rsi_overbought,
+            self.rsi_sky_high,
+        ];
+        if !ascending_within_0_100(&rsi_levels) || self.rsi_buy_threshold >= self.rsi_sell_threshold {
+            return invalid(
+                "RSI levels must satisfy 0 <= collapsed <= oversold <= buy < sell <= overbought <= sky_high <= 100"
+                    .to_string(),
+            );
+        }
+        if !ascending_within_0_100(&[self.failure_swing_oversold, self.failure_swing_overbought]) {
+            return invalid("failure swing levels must be ascending within 0..=100".to_string());
+        }
+        if !ascending_within_0_100(&[self.stochastic_oversold, self.stochastic_overbought]) {
+            return invalid("stochastic levels must be ascending within 0..=100".to_string());
+        }
+
+        let positive = [
+            ("volatility_halt_threshold", self.volatility_halt_threshold),
+            ("action_score_threshold", self.action_score_threshold),
+            ("confidence_score_scale", self.confidence_score_scale),
+        ];
+        if let Some((name, _)) = positive.iter().find(|(_, v)| !(v.is_finite() && *v > 0.0)) {
+            return invalid(format!("{} must be positive", name));
+        }
+
+        let unit = [
+            ("ofi_significant_threshold", self.ofi_significant_threshold),
+            ("trend_strength_threshold", self.trend_strength_threshold),
+            ("min_confidence_threshold", self.min_confidence_threshold),
+            ("neural_override_confidence", self.neural_override_confidence),
+            ("consensus_bonus", self.consensus_bonus),
+            ("standalone_execute_confidence", self.standalone_execute_confidence),
+        ];
+        if let Some((name, _)) = unit.iter().find(|(_, v)| !(0.0..=1.0).contains(v)) {
+            return invalid(format!("{} must be between 0 and 1", name));
+        }
+
+        let w = &self.weights;
+        let weights = [
+            w.rsi_extreme,
+            w.rsi_strong,
+            w.rsi_mild,
+            w.ofi_wall,
+            w.failure_swing,
+            w.bollinger,
+            w.stochastic,
+            w.trend_confirmation,
+            w.macd_confirmation,
+        ];
+        if weights.iter().any(|w| !(w.is_finite() && *w >= 0.0)) {
+            return invalid("score weights must be finite and non-negative".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+fn ascending_within_0_100(levels: &[f64]) -> bool {
+    levels.iter().all(|l| (0.0..=100.0).contains(l)) && levels.windows(2).all(|w| w[0] <= w[1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_are_valid_and_partial_json_fills_in() {
+        GuardianConfig::default().validate().unwrap();
+
+        let config = GuardianConfig::from_json(r#"{"max_latency_ms": 50, "weights": {"ofi_wall": 3.0}}"#).unwrap();
+        assert_eq!(config.max_latency_ms, 50);
+        assert_eq!(config.weights.ofi_wall, 3.0);
+        assert_eq!(config.weights.rsi_extreme, 4.0);
+        assert_eq!(config.rsi_buy_threshold, crate::RSI_BUY_THRESHOLD);
+    }
+
+    #[test]
+    fn test_bad_configs_are_rejected() {
+        let malformed = GuardianConfig::from_json(r#"{"max_latency": 50}"#).unwrap_err();
+        assert!(matches!(malformed, ConfigError::Malformed(_)));
+
+        let cases = [
+            r#"{"buffer_size": 5}"#,
+            r#"{"buffer_size": 100001}"#,
+            r#"{"buffer_size": 18446744073709551615}"#,
+            r#"{"ofi_history_size": 100001}"#,
+            r#"{"rsi_period": 0}"#,
+            r#"{"rsi_buy_threshold": 60.0}"#,
+            r#"{"volatility_halt_threshold": -1.0}"#,
+            r#"{"consensus_bonus": 1.5}"#,
+            r#"{"indicators": {"macd_fast": 30}}"#,
+            r#"{"weights": {"bollinger": -1.0}}"#,
+        ];
+        for json in cases {
+            let error = GuardianConfig::from_json(json).unwrap_err();
+            assert!(matches!(error, ConfigError::Invalid(_)), "{} -> {}", json, error);
+        }
+    }
+}
@@ -0,0 +1,988 @@
+//! Streaming indicators: fed one price or bar at a time, O(1) work per update,
+//! so the guardian can keep them current on every tick.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// An indicator instance parameterised by its lookback.
+pub trait Periodic {
+    fn period(&self) -> usize;
+}
+
+/// Periods kept per indicator. Each tracked period is updated on every
+/// input, so callers can't grow the per-tick cost without bound.
+pub const MAX_TRACKED_PERIODS: usize = 8;
+
+/// Read the tracker for `period`, created by `seed` the first time it is
+/// requested. Once `MAX_TRACKED_PERIODS` are tracked, an untracked period is
+/// seeded afresh on every call and not kept.
+pub fn read_tracker<T: Periodic, V>(
+    trackers: &mut Vec<T>,
+    period: usize,
+    seed: impl FnOnce() -> T,
+    read: impl FnOnce(&T) -> V,
+) -> V {
+    if let Some(tracked) = trackers.iter().find(|t| t.period() == period) {
+        return read(tracked);
+    }
+    let seeded = seed();
+    let value = read(&seeded);
+    if trackers.len() < MAX_TRACKED_PERIODS {
+        trackers.push(seeded);
+    }
+    value
+}
+
+/// Sum of the last `window` inputs.
+#[derive(Debug, Clone)]
+pub struct RollingSum {
+    window: usize,
+    values: VecDeque<f64>,
+    sum: f64,
+    evictions: usize,
+}
+
+impl RollingSum {
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            window,
+            values: VecDeque::with_capacity(window + 1),
+            sum: 0.0,
+            evictions: 0,
+        }
+    }
+
+    pub fn update(&mut self, x: f64) -> f64 {
+        self.values.push_back(x);
+        self.sum += x;
+        if self.values.len() > self.window {
+            let evicted = self.values.pop_front().unwrap_or(0.0);
+            self.sum -= evicted;
+            self.evictions += 1;
+            // Re-add from scratch once per window so subtraction error can't
+            // accumulate; amortised O(1)
+            if self.evictions >= self.window {
+                self.sum = self.values.iter().sum();
+                self.evictions = 0;
+            }
+        }
+        self.sum
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// Inputs currently in the window (at most `window`)
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.values.len() == self.window
+    }
+}
+
+/// Simple moving average over the last `period` inputs.
+#[derive(Debug, Clone)]
+pub struct Sma {
+    sum: RollingSum,
+}
+
+impl Sma {
+    pub fn new(period: usize) -> Self {
+        Self {
+            sum: RollingSum::new(period),
+        }
+    }
+
+    pub fn update(&mut self, x: f64) -> Option<f64> {
+        self.sum.update(x);
+        self.value()
+    }
+
+    /// None until the window is full
+    pub fn value(&self) -> Option<f64> {
+        self.sum.is_full().then(|| self.sum.sum() / self.sum.len() as f64)
+    }
+}
+
+/// Exponential moving average (alpha = 2 / (period + 1)), seeded with the
+/// simple mean of the first `period` inputs.
+#[derive(Debug, Clone)]
+pub struct Ema {
+    period: usize,
+    count: usize,
+    value: f64,
+}
+
+impl Ema {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            count: 0,
+            value: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, x: f64) -> Option<f64> {
+        self.count += 1;
+        if self.count < self.period {
+            self.value += x;
+        } else if self.count == self.period {
+            self.value = (self.value + x) / self.period as f64;
+        } else {
+            let alpha = 2.0 / (self.period as f64 + 1.0);
+            self.value += alpha * (x - self.value);
+        }
+        self.value()
+    }
+
+    pub fn value(&self) -> Option<f64> {
+        (self.count >= self.period).then_some(self.value)
+    }
+}
+
+/// Population mean and variance of the last `window` inputs, maintained
+/// with Welford's update and its inverse for the evicted input.
+#[derive(Debug, Clone)]
+pub struct RollingVariance {
+    window: usize,
+    values: VecDeque<f64>,
+    mean: f64,
+    m2: f64,
+    evictions: usize,
+}
+
+impl RollingVariance {
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            window,
+            values: VecDeque::with_capacity(window + 1),
+            mean: 0.0,
+            m2: 0.0,
+            evictions: 0,
+        }
+    }
+
+    pub fn update(&mut self, x: f64) {
+        self.values.push_back(x);
+        let n = self.values.len() as f64;
+        let delta = x - self.mean;
+        self.mean += delta / n;
+        self.m2 += delta * (x - self.mean);
+
+        if self.values.len() > self.window {
+            let old = self.values.pop_front().unwrap_or(0.0);
+            let n = self.values.len() as f64;
+            let delta = old - self.mean;
+            self.mean -= delta / n;
+            self.m2 -= delta * (old - self.mean);
+            // Guard against a tiny negative from cancellation
+            self.m2 = self.m2.max(0.0);
+            self.evictions += 1;
+            // As for RollingSum: re-derive once per window, so neither drift
+            // nor a non-finite input that has since left the window sticks
+            if self.evictions >= self.window {
+                self.rebuild();
+            }
+        }
+    }
+
+    fn rebuild(&mut self) {
+        let n = self.values.len() as f64;
+        self.mean = self.values.iter().sum::<f64>() / n;
+        self.m2 = self.values.iter().map(|v| (v - self.mean).powi(2)).sum();
+        self.evictions = 0;
+    }
+
+    /// Inputs currently in the window (at most `window`)
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Population variance over the window, 0 when empty
+    pub fn variance(&self) -> f64 {
+        if self.values.is_empty() {
+            0.0
+        } else {
+            self.m2 / self.values.len() as f64
+        }
+    }
+
+    pub fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+}
+
+/// Wilder's moving average (RMA, alpha = 1/period), seeded with the simple
+/// mean of the first `period` inputs.
+#[derive(Debug, Clone)]
+pub struct Rma {
+    period: usize,
+    count: usize,
+    value: f64,
+}
+
+impl Rma {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            count: 0,
+            value: 0.0,
+        }
+    }
+
+    /// Feed the next input. Returns the average once `period` inputs have been seen.
+    pub fn update(&mut self, x: f64) -> Option<f64> {
+        self.count += 1;
+        let n = self.period as f64;
+        if self.count < self.period {
+            // Seeding: accumulate, then turn the sum into a simple mean
+            self.value += x;
+        } else if self.count == self.period {
+            self.value = (self.value + x) / n;
+        } else {
+            self.value = (self.value * (n - 1.0) + x) / n;
+        }
+        self.value()
+    }
+
+    pub fn value(&self) -> Option<f64> {
+        (self.count >= self.period).then_some(self.value)
+    }
+}
+
+/// Wilder's RSI: RMA of gains over RMA of losses, matching exchange and
+/// charting RSI.
+#[derive(Debug, Clone)]
+pub struct WilderRsi {
+    period: usize,
+    last_price: Option<f64>,
+    avg_gain: Rma,
+    avg_loss: Rma,
+}
+
+impl WilderRsi {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            last_price: None,
+            avg_gain: Rma::new(period),
+            avg_loss: Rma::new(period),
+        }
+    }
+
+    /// Feed the next price. Returns the RSI once `period` changes have been seen.
+    pub fn update(&mut self, price: f64) -> Option<f64> {
+        if let Some(last) = self.last_price {
+            let change = price - last;
+            self.avg_gain.update(change.max(0.0));
+            self.avg_loss.update((-change).max(0.0));
+        }
+        self.last_price = Some(price);
+        self.value()
+    }
+
+    /// Current RSI, None while warming up. A flat series reads 50.
+    pub fn value(&self) -> Option<f64> {
+        Some(rsi(self.avg_gain.value()?, self.avg_loss.value()?))
+    }
+}
+
+impl Periodic for WilderRsi {
+    fn period(&self) -> usize {
+        self.period
+    }
+}
+
+/// RSI from average gain and loss (100 with no losses, 50 with no movement).
+pub fn rsi(avg_gain: f64, avg_loss: f64) -> f64 {
+    if avg_loss == 0.0 {
+        return if avg_gain == 0.0 { 50.0 } else { 100.0 };
+    }
+    100.0 - 100.0 / (1.0 + avg_gain / avg_loss)
+}
+
+/// Directional movement at one bar.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dmi {
+    pub plus_di: f64,
+    pub minus_di: f64,
+    /// None until `period` DX values have been averaged
+    pub adx: Option<f64>,
+}
+
+/// Wilder's Directional Movement System: +DI and -DI are the smoothed
+/// directional movements over the smoothed true range, ADX the RMA of DX.
+/// DI is available after `period` bar-to-bar changes, ADX after `2 * period - 1`.
+#[derive(Debug, Clone)]
+pub struct WilderDmi {
+    period: usize,
+    // (high, low, close) of the previous bar
+    prev: Option<(f64, f64, f64)>,
+    true_range: Rma,
+    plus_dm: Rma,
+    minus_dm: Rma,
+    adx: Rma,
+    current: Option<Dmi>,
+}
+
+impl WilderDmi {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            prev: None,
+            true_range: Rma::new(period),
+            plus_dm: Rma::new(period),
+            minus_dm: Rma::new(period),
+            adx: Rma::new(period),
+            current: None,
+        }
+    }
+
+    /// Feed the next bar. Returns the DMI once the directional indicators are warm.
+    pub fn update(&mut self, high: f64, low: f64, close: f64) -> Option<Dmi> {
+        if let Some((prev_high, prev_low, prev_close)) = self.prev {
+            let true_range = (high - low).max((high - prev_close).abs()).max((low - prev_close).abs());
+            let up = high - prev_high;
+            let down = prev_low - low;
+            let plus_dm = if up > down && up > 0.0 { up } else { 0.0 };
+            let minus_dm = if down > up && down > 0.0 { down } else { 0.0 };
+
+            let tr = self.true_range.update(true_range);
+            let plus = self.plus_dm.update(plus_dm);
+            let minus = self.minus_dm.update(minus_dm);
+
+            if let (Some(tr), Some(plus), Some(minus)) = (tr, plus, minus) {
+                let (plus_di, minus_di) = if tr > 0.0 {
+                    (100.0 * plus / tr, 100.0 * minus / tr)
+                } else {
+                    (0.0, 0.0)
+                };
+                let di_sum = plus_di + minus_di;
+                let dx = if di_sum > 0.0 { 100.0 * (plus_di - minus_di).abs() / di_sum } else { 0.0 };
+                self.current = Some(Dmi {
+                    plus_di,
+                    minus_di,
+                    adx: self.adx.update(dx),
+                });
+            }
+        }
+        self.prev = Some((high, low, close));
+        self.current
+    }
+
+    pub fn value(&self) -> Option<Dmi> {
+        self.current
+    }
+}
+
+impl Periodic for WilderDmi {
+    fn period(&self) -> usize {
+        self.period
+    }
+}
+
+/// Maximum or minimum of the last `window` inputs via a monotonic deque:
+/// amortised O(1) per update.
+#[derive(Debug, Clone)]
+pub struct RollingExtreme {
+    window: usize,
+    max: bool,
+    seen: usize,
+    // (input index, value), values monotonic from front to back
+    candidates: VecDeque<(usize, f64)>,
+}
+
+impl RollingExtreme {
+    pub fn max(window: usize) -> Self {
+        Self::new(window, true)
+    }
+
+    pub fn min(window: usize) -> Self {
+        Self::new(window, false)
+    }
+
+    fn new(window: usize, max: bool) -> Self {
+        Self {
+            window: window.max(1),
+            max,
+            seen: 0,
+            candidates: VecDeque::new(),
+        }
+    }
+
+    pub fn update(&mut self, x: f64) -> f64 {
+        let dominated = |v: f64| if self.max { v <= x } else { v >= x };
+        while self.candidates.back().is_some_and(|&(_, v)| dominated(v)) {
+            self.candidates.pop_back();
+        }
+        self.candidates.push_back((self.seen, x));
+        self.seen += 1;
+        while self.candidates.front().is_some_and(|&(i, _)| i + self.window < self.seen) {
+            self.candidates.pop_front();
+        }
+        self.candidates.front().map_or(x, |&(_, v)| v)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.seen >= self.window
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MacdValue {
+    pub macd: f64,
+    pub signal: f64,
+    pub histogram: f64,
+}
+
+/// MACD: fast EMA minus slow EMA, with an EMA of that line as the signal.
+#[derive(Debug, Clone)]
+pub struct Macd {
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+    current: Option<MacdValue>,
+}
+
+impl Macd {
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Self {
+        Self {
+            fast: Ema::new(fast),
+            slow: Ema::new(slow),
+            signal: Ema::new(signal),
+            current: None,
+        }
+    }
+
+    /// Returns a value once the signal line has warmed up
+    pub fn update(&mut self, close: f64) -> Option<MacdValue> {
+        let fast = self.fast.update(close);
+        let slow = self.slow.update(close);
+        if let (Some(fast), Some(slow)) = (fast, slow) {
+            let macd = fast - slow;
+            if let Some(signal) = self.signal.update(macd) {
+                self.current = Some(MacdValue {
+                    macd,
+                    signal,
+                    histogram: macd - signal,
+                });
+            }
+        }
+        self.current
+    }
+
+    pub fn value(&self) -> Option<MacdValue> {
+        self.current
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandsValue {
+    pub upper: f64,
+    pub middle: f64,
+    pub lower: f64,
+    /// Where the close sits in the bands: 0 at the lower, 1 at the upper
+    pub percent_b: f64,
+}
+
+/// Bollinger Bands: SMA of closes plus/minus `width` population standard deviations.
+#[derive(Debug, Clone)]
+pub struct Bollinger {
+    period: usize,
+    width: f64,
+    closes: RollingVariance,
+    current: Option<BandsValue>,
+}
+
+impl Bollinger {
+    pub fn new(period: usize, width: f64) -> Self {
+        Self {
+            period: period.max(1),
+            width,
+            closes: RollingVariance::new(period),
+            current: None,
+        }
+    }
+
+    pub fn update(&mut self, close: f64) -> Option<BandsValue> {
+        self.closes.update(close);
+        if self.closes.len() >= self.period {
+            let middle = self.closes.mean();
+            let offset = self.width * self.closes.std_dev();
+            let (upper, lower) = (middle + offset, middle - offset);
+            self.current = Some(BandsValue {
+                upper,
+                middle,
+                lower,
+                // Collapsed bands put every close in the middle
+                percent_b: if upper > lower { (close - lower) / (upper - lower) } else { 0.5 },
+            });
+        }
+        self.current
+    }
+
+    pub fn value(&self) -> Option<BandsValue> {
+        self.current
+    }
+}
+
+/// Average True Range with Wilder smoothing. The first bar only provides a
+/// previous close, as for DMI.
+#[derive(Debug, Clone)]
+pub struct Atr {
+    prev_close: Option<f64>,
+    true_range: Rma,
+}
+
+impl Atr {
+    pub fn new(period: usize) -> Self {
+        Self {
+            prev_close: None,
+            true_range: Rma::new(period),
+        }
+    }
+
+    pub fn update(&mut self, high: f64, low: f64, close: f64) -> Option<f64> {
+        if let Some(prev) = self.prev_close {
+            self.true_range.update((high - low).max((high - prev).abs()).max((low - prev).abs()));
+        }
+        self.prev_close = Some(close);
+        self.value()
+    }
+
+    pub fn value(&self) -> Option<f64> {
+        self.true_range.value()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StochasticValue {
+    pub k: f64,
+    /// SMA of %K, None until enough %K values exist
+    pub d: Option<f64>,
+}
+
+/// Fast Stochastic: %K is the close's position in the `k_period` high-low
+/// range, %D its `d_period` SMA.
+#[derive(Debug, Clone)]
+pub struct Stochastic {
+    highs: RollingExtreme,
+    lows: RollingExtreme,
+    d: Sma,
+    current: Option<StochasticValue>,
+}
+
+impl Stochastic {
+    pub fn new(k_period: usize, d_period: usize) -> Self {
+        Self {
+            highs: RollingExtreme::max(k_period),
+            lows: RollingExtreme::min(k_period),
+            d: Sma::new(d_period),
+            current: None,
+        }
+    }
+
+    pub fn update(&mut self, high: f64, low: f64, close: f64) -> Option<StochasticValue> {
+        let highest = self.highs.update(high);
+        let lowest = self.lows.update(low);
+        if self.highs.is_full() {
+            // A flat range reads as mid-range
+            let k = if highest > lowest { 100.0 * (close - lowest) / (highest - lowest) } else { 50.0 };
+            self.current = Some(StochasticValue { k, d: self.d.update(k) });
+        }
+        self.current
+    }
+
+    pub fn value(&self) -> Option<StochasticValue> {
+        self.current
+    }
+}
+
+/// Lookbacks for the classic bar indicator set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IndicatorParams {
+    pub sma_period: usize,
+    pub ema_period: usize,
+    pub macd_fast: usize,
+    pub macd_slow: usize,
+    pub macd_signal: usize,
+    pub bollinger_period: usize,
+    pub bollinger_width: f64,
+    pub atr_period: usize,
+    pub stochastic_k: usize,
+    pub stochastic_d: usize,
+}
+
+impl Default for IndicatorParams {
+    fn default() -> Self {
+        Self {
+            sma_period: 20,
+            ema_period: 20,
+            macd_fast: 12,
+            macd_slow: 26,
+            macd_signal: 9,
+            bollinger_period: 20,
+            bollinger_width: 2.0,
+            atr_period: 14,
+            stochastic_k: 14,
+            stochastic_d: 3,
+        }
+    }
+}
+
+/// Current values of the classic set; each None while it warms up.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct IndicatorValues {
+    pub sma: Option<f64>,
+    pub ema: Option<f64>,
+    pub macd: Option<MacdValue>,
+    pub bands: Option<BandsValue>,
+    pub atr: Option<f64>,
+    pub stochastic: Option<StochasticValue>,
+}
+
+/// SMA, EMA, MACD, Bollinger Bands, ATR and Stochastic, all fed from the
+/// same bars.
+#[derive(Debug, Clone)]
+pub struct BarIndicators {
+    sma: Sma,
+    ema: Ema,
+    macd: Macd,
+    bollinger: Bollinger,
+    atr: Atr,
+    stochastic: Stochastic,
+}
+
+impl BarIndicators {
+    pub fn new(params: &IndicatorParams) -> Self {
+        Self {
+            sma: Sma::new(params.sma_period),
+            ema: Ema::new(params.ema_period),
+            macd: Macd::new(params.macd_fast, params.macd_slow, params.macd_signal),
+            bollinger: Bollinger::new(params.bollinger_period, params.bollinger_width),
+            atr: Atr::new(params.atr_period),
+            stochastic: Stochastic::new(params.stochastic_k, params.stochastic_d),
+        }
+    }
+
+    pub fn update(&mut self, high: f64, low: f64, close: f64) {
+        self.sma.update(close);
+        self.ema.update(close);
+        self.macd.update(close);
+        self.bollinger.update(close);
+        self.atr.update(high, low, close);
+        self.stochastic.update(high, low, close);
+    }
+
+    pub fn values(&self) -> IndicatorValues {
+        IndicatorValues {
+            sma: self.sma.value(),
+            ema: self.ema.value(),
+            macd: self.macd.value(),
+            bands: self.bollinger.value(),
+            atr: self.atr.value(),
+            stochastic: self.stochastic.value(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Batch references: recompute each value from the full series
+    fn series() -> Vec<f64> {
+        (0..300).map(|i| 100.0 + 10.0 * (i as f64 / 7.0).sin() + 0.05 * i as f64).collect()
+    }
+
+    #[test]
+    fn test_moving_averages_match_batch() {
+        let xs = series();
+        let (mut sma, mut ema, mut sum) = (Sma::new(20), Ema::new(20), RollingSum::new(20));
+        let mut batch_ema = None;
+
+        for (i, x) in xs.iter().enumerate() {
+            let window = &xs[i.saturating_sub(19)..=i];
+            assert!((sum.update(*x) - window.iter().sum::<f64>()).abs() < 1e-9);
+
+            let expected_sma = (i >= 19).then(|| window.iter().sum::<f64>() / 20.0);
+            let got = sma.update(*x);
+            assert_eq!(got.is_some(), expected_sma.is_some());
+            assert!((got.unwrap_or(0.0) - expected_sma.unwrap_or(0.0)).abs() < 1e-9);
+
+            batch_ema = match batch_ema {
+                None if i == 19 => expected_sma,
+                Some(prev) => Some(prev + 2.0 / 21.0 * (x - prev)),
+                None => None,
+            };
+            assert_eq!(ema.update(*x), batch_ema);
+        }
+    }
+
+    #[test]
+    fn test_rolling_variance_matches_batch() {
+        let xs = series();
+        let mut variance = RollingVariance::new(50);
+
+        for (i, x) in xs.iter().enumerate() {
+            variance.update(*x);
+            let window = &xs[i.saturating_sub(49)..=i];
+            let mean = window.iter().sum::<f64>() / window.len() as f64;
+            let expected = window.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / window.len() as f64;
+            assert_eq!(variance.len(), window.len());
+            assert!((variance.mean() - mean).abs() < 1e-9);
+            assert!((variance.variance() - expected).abs() < 1e-8, "{} vs {}", variance.variance(), expected);
+        }
+    }
+
+    #[test]
+    fn test_rolling_variance_recovers_from_non_finite_input() {
+        let mut variance = RollingVariance::new(10);
+        variance.update(f64::INFINITY);
+        for i in 0..30 {
+            variance.update(if i % 2 == 0 { 1.0 } else { 3.0 });
+        }
+        assert!((variance.mean() - 2.0).abs() < 1e-12);
+        assert!((variance.variance() - 1.0).abs() < 1e-12);
+    }
+
+    // Wilder's 14-period sample series (StockCharts RSI worksheet)
+    const PRICES: [f64; 33] = [
+        44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03, 45.61, 46.28, 46.28,
+        46.00, 46.03, 46.41, 46.22, 45.64, 46.21, 46.25, 45.71, 46.45, 45.78, 45.35, 44.03, 44.18, 44.22, 44.57,
+        43.42, 42.66, 43.13,
+    ];
+
+    #[test]
+    fn test_wilder_rsi_matches_reference() {
+        // Full-precision values; the worksheet rounds its intermediate
+        // averages, so its published figures differ in the second decimal
+        let expected = [
+            70.46, 66.25, 66.48, 69.35, 66.29, 57.92, 62.88, 63.21, 56.01, 62.34, 54.67, 50.39, 40.02, 41.49,
+            41.90, 45.50, 37.32, 33.09, 37.79,
+        ];
+
+        let mut rsi = WilderRsi::new(14);
+        let values: Vec<f64> = PRICES.iter().filter_map(|p| rsi.update(*p)).collect();
+
+        assert_eq!(values.len(), expected.len());
+        for (value, expected) in values.iter().zip(expected) {
+            assert!((value - expected).abs() < 0.01, "{} vs {}", value, expected);
+        }
+    }
+
+    #[test]
+    fn test_wilder_rsi_warm_up_and_flat_series() {
+        let mut rsi = WilderRsi::new(3);
+        assert_eq!(rsi.update(10.0), None);
+        assert_eq!(rsi.update(11.0), None);
+        assert_eq!(rsi.update(12.0), None);
+        assert_eq!(rsi.update(13.0), Some(100.0));
+
+        let mut flat = WilderRsi::new(2);
+        for _ in 0..5 {
+            flat.update(100.0);
+        }
+        assert_eq!(flat.value(), Some(50.0));
+    }
+
+    // Textbook DMI with Wilder's running-sum smoothing, over the whole series
+    fn batch_dmi(bars: &[(f64, f64, f64)], period: usize) -> Vec<Dmi> {
+        let n = period as f64;
+        let mut sums = (0.0, 0.0, 0.0);
+        let mut dx = Vec::new();
+        let mut adx: Option<f64> = None;
+        let mut out = Vec::new();
+
+        for (i, w) in bars.windows(2).enumerate() {
+            let ((ph, pl, pc), (h, l, _)) = (w[0], w[1]);
+            let tr = (h - l).max((h - pc).abs()).max((l - pc).abs());
+            let (up, down) = (h - ph, pl - l);
+            let pdm = if up > down && up > 0.0 { up } else { 0.0 };
+            let mdm = if down > up && down > 0.0 { down } else { 0.0 };
+
+            if i < period {
+                sums = (sums.0 + tr, sums.1 + pdm, sums.2 + mdm);
+            } else {
+                sums = (sums.0 - sums.0 / n + tr, sums.1 - sums.1 / n + pdm, sums.2 - sums.2 / n + mdm);
+            }
+            if i + 1 < period {
+                continue;
+            }
+
+            let (plus_di, minus_di) = (100.0 * sums.1 / sums.0, 100.0 * sums.2 / sums.0);
+            dx.push(100.0 * (plus_di - minus_di).abs() / (plus_di + minus_di));
+            adx = match (adx, dx.len()) {
+                (Some(prev), _) => Some((prev * (n - 1.0) + dx[dx.len() - 1]) / n),
+                (None, len) if len == period => Some(dx.iter().sum::<f64>() / n),
+                (None, _) => None,
+            };
+            out.push(Dmi { plus_di, minus_di, adx });
+        }
+        out
+    }
+
+    #[test]
+    fn test_wilder_dmi_matches_batch_reference() {
+        let bars: Vec<(f64, f64, f64)> = (0..60)
+            .map(|i| {
+                let t = i as f64;
+                let mid = 100.0 + 0.3 * t + 4.0 * (t / 5.0).sin();
+                let range = 1.0 + 0.5 * (t / 3.0).cos().abs();
+                (mid + range, mid - range, mid + 0.4 * (t / 2.0).sin())
+            })
+            .collect();
+
+        let mut dmi = WilderDmi::new(14);
+        let streamed: Vec<Dmi> = bars.iter().filter_map(|&(h, l, c)| dmi.update(h, l, c)).collect();
+        let reference = batch_dmi(&bars, 14);
+
+        assert_eq!(streamed.len(), reference.len());
+        for (s, r) in streamed.iter().zip(&reference) {
+            assert!((s.plus_di - r.plus_di).abs() < 1e-9);
+            assert!((s.minus_di - r.minus_di).abs() < 1e-9);
+            assert_eq!(s.adx.is_some(), r.adx.is_some());
+            assert!((s.adx.unwrap_or(0.0) - r.adx.unwrap_or(0.0)).abs() < 1e-9);
+        }
+        // ADX first appears after 2 * period - 1 changes
+        assert_eq!(streamed.iter().position(|d| d.adx.is_some()), Some(13));
+    }
+
+    #[test]
+    fn test_wilder_dmi_steady_uptrend() {
+        let mut dmi = WilderDmi::new(5);
+        let mut last = None;
+        for i in 0..20 {
+            let base = 100.0 + i as f64;
+            last = dmi.update(base + 1.0, base - 1.0, base);
+        }
+        let last = last.unwrap();
+        assert_eq!(last.minus_di, 0.0);
+        assert!(last.plus_di > 0.0);
+        assert!((last.adx.unwrap() - 100.0).abs() < 1e-9);
+    }
+
+    fn bars() -> Vec<(f64, f64, f64)> {
+        series()
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let spread = 0.5 + (i as f64 / 4.0).cos().abs();
+                (c + spread, c - spread * 0.8, *c)
+            })
+            .collect()
+    }
+
+    fn batch_ema(xs: &[f64], period: usize) -> Vec<Option<f64>> {
+        let alpha = 2.0 / (period as f64 + 1.0);
+        let mut out = Vec::new();
+        let mut value: Option<f64> = None;
+        for i in 0..xs.len() {
+            value = match value {
+                Some(prev) => Some(prev + alpha * (xs[i] - prev)),
+                None if i + 1 == period => Some(xs[..period].iter().sum::<f64>() / period as f64),
+                None => None,
+            };
+            out.push(value);
+        }
+        out
+    }
+
+    #[test]
+    fn test_macd_matches_batch() {
+        let closes = series();
+        let fast = batch_ema(&closes, 12);
+        let slow = batch_ema(&closes, 26);
+        let line: Vec<f64> = fast.iter().zip(&slow).filter_map(|(f, s)| Some((*f)? - (*s)?)).collect();
+        let signal = batch_ema(&line, 9);
+
+        let mut macd = Macd::new(12, 26, 9);
+        let streamed: Vec<MacdValue> = closes.iter().filter_map(|c| macd.update(*c)).collect();
+        let expected: Vec<(f64, f64)> = line.iter().zip(&signal).filter_map(|(m, s)| Some((*m, (*s)?))).collect();
+
+        // First value after slow + signal - 1 closes
+        assert_eq!(streamed.len(), closes.len() - 33);
+        assert_eq!(streamed.len(), expected.len());
+        for (got, (m, s)) in streamed.iter().zip(expected) {
+            assert!((got.macd - m).abs() < 1e-9 && (got.signal - s).abs() < 1e-9);
+            assert!((got.histogram - (m - s)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_bollinger_matches_batch() {
+        let closes = series();
+        let mut bollinger = Bollinger::new(20, 2.0);
+
+        for (i, close) in closes.iter().enumerate() {
+            let got = bollinger.update(*close);
+            if i < 19 {
+                assert!(got.is_none());
+                continue;
+            }
+            let window = &closes[i - 19..=i];
+            let mean = window.iter().sum::<f64>() / 20.0;
+            let sd = (window.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / 20.0).sqrt();
+            let got = got.unwrap();
+            assert!((got.middle - mean).abs() < 1e-9);
+            assert!((got.upper - (mean + 2.0 * sd)).abs() < 1e-8);
+            assert!((got.percent_b - (close - (mean - 2.0 * sd)) / (4.0 * sd)).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn test_atr_and_stochastic_match_batch() {
+        let bars = bars();
+        let true_ranges: Vec<f64> = bars
+            .windows(2)
+            .map(|w| {
+                let ((_, _, pc), (h, l, _)) = (w[0], w[1]);
+                (h - l).max((h - pc).abs()).max((l - pc).abs())
+            })
+            .collect();
+        let mut expected_atr = true_ranges[..14].iter().sum::<f64>() / 14.0;
+        for tr in &true_ranges[14..] {
+            expected_atr = (expected_atr * 13.0 + tr) / 14.0;
+        }
+
+        let mut atr = Atr::new(14);
+        let mut stochastic = Stochastic::new(14, 3);
+        let mut ks = Vec::new();
+        for (i, &(h, l, c)) in bars.iter().enumerate() {
+            atr.update(h, l, c);
+            let got = stochastic.update(h, l, c);
+            if i < 13 {
+                assert!(got.is_none());
+                continue;
+            }
+            let window = &bars[i - 13..=i];
+            let highest = window.iter().map(|b| b.0).fold(f64::MIN, f64::max);
+            let lowest = window.iter().map(|b| b.1).fold(f64::MAX, f64::min);
+            let k = 100.0 * (c - lowest) / (highest - lowest);
+            ks.push(k);
+            let got = got.unwrap();
+            assert!((got.k - k).abs() < 1e-9);
+            let d = (ks.len() >= 3).then(|| ks[ks.len() - 3..].iter().sum::<f64>() / 3.0);
+            assert!((got.d.unwrap_or(0.0) - d.unwrap_or(0.0)).abs() < 1e-9);
+        }
+        assert!((atr.value().unwrap() - expected_atr).abs() < 1e-9);
+    }
+}
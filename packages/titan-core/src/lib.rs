@@ -2,7 +2,11 @@
 //! 
 //! A deterministic, WASM-compiled trading logic module that implements:
 //! - Order Flow Imbalance (OFI) Matrix calculation with SIMD
-//! - RSI Failure Swing detection
+//! - Wilder RSI maintained incrementally per tick, and RSI Failure Swing detection
+//! - Wilder ADX / +DI / -DI from bar data for regime filtering
+//! - Time, tick, volume and dollar bars aggregated from ticks
+//! - SMA, EMA, MACD, Bollinger Bands, ATR and Stochastic from bars
+//! - Thresholds, weights and windows set through `GuardianConfig`
 //! - Volatility-triggered HALT mechanism
 //! - Cryptographic proof generation for on-chain verification
 //!
@@ -20,8 +24,20 @@ use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
 use std::collections::VecDeque;
 
+mod bars;
+mod config;
+pub mod indicators;
+
+pub use bars::BarType;
+pub use config::{ConfigError, GuardianConfig, ScoreWeights};
+use bars::BarSeries;
+use indicators::{
+    read_tracker, BarIndicators, Dmi, IndicatorValues, RollingSum, RollingVariance, WilderDmi, WilderRsi,
+};
+
 // ============================================================================
 // CONSTANTS & THRESHOLDS (Data-Driven from Golden Dataset Analysis)
+// Defaults for GuardianConfig
 // ============================================================================
 
 /// RSI threshold for BUY signal (from Avg winning RSI = 45.9)
@@ -32,6 +48,10 @@ const RSI_SELL_THRESHOLD: f64 = 59.0;
 const RSI_COLLAPSED: f64 = 25.0;
 /// RSI extreme overbought
 const RSI_SKY_HIGH: f64 = 75.0;
+/// RSI lookback used for signal scoring and failure swings
+const RSI_PERIOD: usize = 14;
+/// Default ADX / DMI lookback, tracked from the first bar
+const ADX_PERIOD: usize = 14;
 /// Volatility threshold for HALT (standard deviations)
 const VOLATILITY_HALT_THRESHOLD: f64 = 3.5;
 /// Maximum network latency before HALT (milliseconds)
@@ -73,6 +93,8 @@ pub struct TradingSignal {
     proof_hash: String,
     timestamp: u64,
     can_execute: bool,
+    // GuardianConfig the signal was scored under, as JSON
+    config: String,
 }
 
 #[wasm_bindgen]
@@ -101,17 +123,133 @@ impl TradingSignal {
     pub fn can_execute(&self) -> bool {
         self.can_execute
     }
+
+    /// The GuardianConfig (JSON) in force when this signal was generated;
+    /// its SHA-256 is part of `proof_hash`
+    #[wasm_bindgen(getter)]
+    pub fn config(&self) -> String {
+        self.config.clone()
+    }
 }
 
 #[wasm_bindgen]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketTick {
     pub price: f64,
+    /// Book depth at the time of the tick (OFI input)
     pub bid_volume: f64,
     pub ask_volume: f64,
+    /// Size traded since the previous tick, 0 for book-only updates
+    #[serde(default)]
+    pub traded_volume: f64,
     pub timestamp: u64,
 }
 
+/// Wilder's directional movement reading. All zero while warming up.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DmiReading {
+    plus_di: f64,
+    minus_di: f64,
+    adx: f64,
+    ready: bool,
+}
+
+#[wasm_bindgen]
+impl DmiReading {
+    #[wasm_bindgen(getter)]
+    pub fn plus_di(&self) -> f64 {
+        self.plus_di
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn minus_di(&self) -> f64 {
+        self.minus_di
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn adx(&self) -> f64 {
+        self.adx
+    }
+
+    /// True once ADX itself has warmed up (2 * period - 1 bar changes)
+    #[wasm_bindgen(getter)]
+    pub fn ready(&self) -> bool {
+        self.ready
+    }
+}
+
+/// Classic indicator set over bar closes. Each getter is undefined until
+/// that indicator has warmed up.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IndicatorReading {
+    values: IndicatorValues,
+}
+
+#[wasm_bindgen]
+impl IndicatorReading {
+    #[wasm_bindgen(getter)]
+    pub fn sma(&self) -> Option<f64> {
+        self.values.sma
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn ema(&self) -> Option<f64> {
+        self.values.ema
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn macd(&self) -> Option<f64> {
+        self.values.macd.map(|m| m.macd)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn macd_signal(&self) -> Option<f64> {
+        self.values.macd.map(|m| m.signal)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn macd_histogram(&self) -> Option<f64> {
+        self.values.macd.map(|m| m.histogram)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn bollinger_upper(&self) -> Option<f64> {
+        self.values.bands.map(|b| b.upper)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn bollinger_middle(&self) -> Option<f64> {
+        self.values.bands.map(|b| b.middle)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn bollinger_lower(&self) -> Option<f64> {
+        self.values.bands.map(|b| b.lower)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn percent_b(&self) -> Option<f64> {
+        self.values.bands.map(|b| b.percent_b)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn atr(&self) -> Option<f64> {
+        self.values.atr
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn stochastic_k(&self) -> Option<f64> {
+        self.values.stochastic.map(|s| s.k)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn stochastic_d(&self) -> Option<f64> {
+        self.values.stochastic.and_then(|s| s.d)
+    }
+}
+
 #[wasm_bindgen]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OFIMatrix {
@@ -152,7 +290,33 @@ pub struct SiliconMathGuardian {
     kill_switch_active: bool,
     audit_log: Vec<String>,
     last_volatility: f64,
-    buffer_size: usize,
+    config: GuardianConfig,
+    // Serialised once; echoed in every signal
+    config_json: String,
+    // Wilder RSI per requested period, updated on every tick
+    rsi_trackers: Vec<WilderRsi>,
+    // (high, low, close) of recent bars, for seeding newly requested DMI periods
+    bar_history: VecDeque<(f64, f64, f64)>,
+    // Wilder DMI per requested period, updated on every bar
+    dmi_trackers: Vec<WilderDmi>,
+    // Classic indicator set over ingested bars, scored in generate_signal
+    bar_indicators: BarIndicators,
+    // Bar series built from ingested ticks, addressed by index
+    bar_series: Vec<BarSeries>,
+    // Running OFI sums over the tick buffer
+    bid_volume_sum: RollingSum,
+    ask_volume_sum: RollingSum,
+    // Last 5 and last 10 prices, for momentum
+    recent_price_sum: RollingSum,
+    momentum_price_sum: RollingSum,
+    // Returns across the price buffer, for volatility
+    return_variance: RollingVariance,
+}
+
+impl Default for SiliconMathGuardian {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[wasm_bindgen]
@@ -160,18 +324,22 @@ impl SiliconMathGuardian {
     /// Create a new Math Guardian instance
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
-        #[cfg(feature = "console_error_panic_hook")]
-        console_error_panic_hook::set_once();
+        Self::build(GuardianConfig::default())
+    }
 
-        Self {
-            tick_buffer: VecDeque::with_capacity(100),
-            price_history: VecDeque::with_capacity(100),
-            ofi_history: VecDeque::with_capacity(50),
-            kill_switch_active: false,
-            audit_log: Vec::new(),
-            last_volatility: 0.0,
-            buffer_size: 100,
-        }
+    /// Create a Math Guardian from a (partial) GuardianConfig object. Missing
+    /// fields take their defaults; unknown fields and out-of-range values throw.
+    #[wasm_bindgen]
+    pub fn with_config(config: JsValue) -> Result<SiliconMathGuardian, JsError> {
+        let config: GuardianConfig =
+            serde_wasm_bindgen::from_value(config).map_err(|e| ConfigError::Malformed(e.to_string()))?;
+        Ok(Self::from_config(config)?)
+    }
+
+    /// The GuardianConfig in force, as JSON
+    #[wasm_bindgen]
+    pub fn get_config(&self) -> String {
+        self.config_json.clone()
     }
 
     /// EU AI Act Article 14: Hardware Kill Switch Interface
@@ -181,10 +349,11 @@ impl SiliconMathGuardian {
         self.kill_switch_active = true;
         let log_entry = format!(
             "[KILL_SWITCH] Activated at {} - Reason: {}",
-            js_sys::Date::now() as u64,
+            now_ms(),
             reason
         );
         self.audit_log.push(log_entry);
+        #[cfg(target_arch = "wasm32")]
         web_sys::console::warn_1(&"🛑 TITAN KILL SWITCH ACTIVATED".into());
     }
 
@@ -193,7 +362,7 @@ impl SiliconMathGuardian {
     pub fn deactivate_kill_switch(&mut self, operator_signature: &str) {
         let log_entry = format!(
             "[KILL_SWITCH] Deactivated at {} - Operator: {}",
-            js_sys::Date::now() as u64,
+            now_ms(),
             operator_signature
         );
         self.audit_log.push(log_entry);
@@ -209,30 +378,162 @@ impl SiliconMathGuardian {
     /// Ingest a market tick for OFI calculation
     #[wasm_bindgen]
     pub fn ingest_tick(&mut self, price: f64, bid_vol: f64, ask_vol: f64) {
+        self.ingest_tick_at(price, bid_vol, ask_vol, now_ms());
+    }
+
+    /// Ingest a book-only tick stamped with its exchange time, which time bars
+    /// bucket on. Nothing traded, so volume and dollar bars do not advance.
+    #[wasm_bindgen]
+    pub fn ingest_tick_at(&mut self, price: f64, bid_vol: f64, ask_vol: f64, timestamp_ms: u64) {
+        self.ingest_trade_at(price, bid_vol, ask_vol, 0.0, timestamp_ms);
+    }
+
+    /// Ingest a tick carrying the size traded since the previous one, which
+    /// volume and dollar bars accumulate. Ticks with a non-positive or
+    /// non-finite price, or a negative or non-finite volume, are ignored.
+    /// Bars skip ticks stamped before the last one they took, so stick to
+    /// one clock (don't mix with `ingest_tick`'s wall time).
+    #[wasm_bindgen]
+    pub fn ingest_trade_at(&mut self, price: f64, bid_vol: f64, ask_vol: f64, traded_vol: f64, timestamp_ms: u64) {
+        let valid_volume = |v: f64| v.is_finite() && v >= 0.0;
+        let valid = price.is_finite() && price > 0.0;
+        if !(valid && valid_volume(bid_vol) && valid_volume(ask_vol) && valid_volume(traded_vol)) {
+            return;
+        }
+
         let tick = MarketTick {
             price,
             bid_volume: bid_vol,
             ask_volume: ask_vol,
-            timestamp: js_sys::Date::now() as u64,
+            traded_volume: traded_vol,
+            timestamp: timestamp_ms,
         };
 
+        for series in self.bar_series.iter_mut() {
+            series.push(&tick);
+        }
+        self.bid_volume_sum.update(bid_vol);
+        self.ask_volume_sum.update(ask_vol);
+        self.recent_price_sum.update(price);
+        self.momentum_price_sum.update(price);
+        if let Some(previous) = self.price_history.back() {
+            self.return_variance.update((price - previous) / previous);
+        }
+
         self.tick_buffer.push_back(tick);
         self.price_history.push_back(price);
+        for rsi in self.rsi_trackers.iter_mut() {
+            rsi.update(price);
+        }
 
         // Maintain buffer size
-        if self.tick_buffer.len() > self.buffer_size {
+        if self.tick_buffer.len() > self.config.buffer_size {
             self.tick_buffer.pop_front();
         }
-        if self.price_history.len() > self.buffer_size {
+        if self.price_history.len() > self.config.buffer_size {
             self.price_history.pop_front();
         }
     }
 
+    /// Ingest a completed bar for ADX / DMI and the classic indicator set.
+    /// Bars with a non-finite price or high below low are ignored.
+    #[wasm_bindgen]
+    pub fn ingest_bar(&mut self, high: f64, low: f64, close: f64) {
+        if !(high.is_finite() && low.is_finite() && close.is_finite()) || high < low {
+            return;
+        }
+
+        self.bar_history.push_back((high, low, close));
+        for dmi in self.dmi_trackers.iter_mut() {
+            dmi.update(high, low, close);
+        }
+        self.bar_indicators.update(high, low, close);
+
+        if self.bar_history.len() > self.config.buffer_size {
+            self.bar_history.pop_front();
+        }
+    }
+
+    /// Calculate +DI, -DI and ADX with Wilder smoothing over `period` bars.
+    /// Tracking and late seeding work as for `calculate_rsi`, over the bar
+    /// buffer.
+    #[wasm_bindgen]
+    pub fn calculate_adx(&mut self, period: usize) -> DmiReading {
+        let period = period.max(1);
+        let bars = &self.bar_history;
+        let seed = || {
+            let mut dmi = WilderDmi::new(period);
+            for (high, low, close) in bars.iter() {
+                dmi.update(*high, *low, *close);
+            }
+            dmi
+        };
+        dmi_reading(read_tracker(&mut self.dmi_trackers, period, seed, WilderDmi::value))
+    }
+
+    /// SMA/EMA (20), MACD (12, 26, 9), Bollinger Bands (20, 2σ), ATR (14) and
+    /// Stochastic (14, 3) over the bars passed to `ingest_bar`
+    #[wasm_bindgen]
+    pub fn get_indicators(&self) -> IndicatorReading {
+        IndicatorReading {
+            values: self.bar_indicators.values(),
+        }
+    }
+
+    /// Start aggregating ingested ticks into bars of `kind`, closing every
+    /// `size` (ms, ticks, traded volume or traded notional). Returns the series id, or
+    /// undefined if `size` is not a usable threshold.
+    #[wasm_bindgen]
+    pub fn add_bar_series(&mut self, kind: BarType, size: f64) -> Option<usize> {
+        let series = BarSeries::new(kind, size, self.config.buffer_size, &self.config.indicators)?;
+        self.bar_series.push(series);
+        Some(self.bar_series.len() - 1)
+    }
+
+    /// Number of completed bars held for a series (0 for an unknown id)
+    #[wasm_bindgen]
+    pub fn bar_count(&self, series: usize) -> usize {
+        self.bar_series.get(series).map_or(0, |s| s.bars().len())
+    }
+
+    /// Completed bars of a series as a JSON array, oldest first
+    #[wasm_bindgen]
+    pub fn get_bars(&self, series: usize) -> String {
+        self.bar_series
+            .get(series)
+            .and_then(|s| serde_json::to_string(s.bars()).ok())
+            .unwrap_or_else(|| "[]".to_string())
+    }
+
+    /// Wilder RSI over the closes of a bar series. Neutral (50) while warming
+    /// up or for an unknown id.
+    #[wasm_bindgen]
+    pub fn calculate_bar_rsi(&mut self, series: usize, period: usize) -> f64 {
+        self.bar_series
+            .get_mut(series)
+            .and_then(|s| s.rsi(period.max(1)))
+            .unwrap_or(50.0)
+    }
+
+    /// The classic indicator set over a bar series (all undefined for an unknown id)
+    #[wasm_bindgen]
+    pub fn calculate_bar_indicators(&self, series: usize) -> IndicatorReading {
+        IndicatorReading {
+            values: self.bar_series.get(series).map(|s| s.indicators()).unwrap_or_default(),
+        }
+    }
+
+    /// ADX / DMI over a bar series, zeroed while warming up or for an unknown id
+    #[wasm_bindgen]
+    pub fn calculate_bar_adx(&mut self, series: usize, period: usize) -> DmiReading {
+        dmi_reading(self.bar_series.get_mut(series).and_then(|s| s.dmi(period.max(1))))
+    }
+
     /// Calculate Order Flow Imbalance Matrix using SIMD-optimized operations
     /// This is the core "Hot Path" function - must be < 1ms
     #[wasm_bindgen]
     pub fn calculate_ofi(&mut self) -> OFIMatrix {
-        if self.tick_buffer.len() < 10 {
+        if self.tick_buffer.len() < self.config.min_ticks_for_ofi {
             return OFIMatrix {
                 imbalance: 0.0,
                 cumulative_delta: 0.0,
@@ -242,16 +543,10 @@ impl SiliconMathGuardian {
             };
         }
 
-        let mut total_bid = 0.0;
-        let mut total_ask = 0.0;
-        let mut delta_sum = 0.0;
-
-        // SIMD-friendly loop (compiler will auto-vectorize)
-        for tick in self.tick_buffer.iter() {
-            total_bid += tick.bid_volume;
-            total_ask += tick.ask_volume;
-            delta_sum += tick.bid_volume - tick.ask_volume;
-        }
+        // Running sums over the same window as the tick buffer
+        let total_bid = self.bid_volume_sum.sum();
+        let total_ask = self.ask_volume_sum.sum();
+        let delta_sum = total_bid - total_ask;
 
         let total_volume = total_bid + total_ask;
         let imbalance = if total_volume > 0.0 {
@@ -261,11 +556,10 @@ impl SiliconMathGuardian {
         };
 
         // Calculate trend strength from price momentum
-        let prices: Vec<f64> = self.price_history.iter().copied().collect();
-        let trend_strength = self.calculate_momentum(&prices);
+        let trend_strength = self.calculate_momentum();
 
         self.ofi_history.push_back(imbalance);
-        if self.ofi_history.len() > 50 {
+        if self.ofi_history.len() > self.config.ofi_history_size {
             self.ofi_history.pop_front();
         }
 
@@ -278,9 +572,33 @@ impl SiliconMathGuardian {
         }
     }
 
-    /// Calculate RSI using the Wilder smoothing method
+    /// Calculate RSI using the Wilder smoothing method (RMA of gains and losses).
+    /// Neutral (50) while warming up.
+    ///
+    /// The configured `rsi_period` is tracked from the first tick. Any other
+    /// period is seeded from the price buffer (the last `buffer_size` ticks)
+    /// on first request and tracked from then on, so a period first asked
+    /// for late can differ from one tracked throughout until the smoothing
+    /// has forgotten the seed. At most `MAX_TRACKED_PERIODS` are tracked;
+    /// further periods are re-seeded from the buffer on every call.
+    #[wasm_bindgen]
+    pub fn calculate_rsi(&mut self, period: usize) -> f64 {
+        let period = period.max(1);
+        let prices = &self.price_history;
+        let seed = || {
+            let mut rsi = WilderRsi::new(period);
+            for price in prices.iter() {
+                rsi.update(*price);
+            }
+            rsi
+        };
+        read_tracker(&mut self.rsi_trackers, period, seed, WilderRsi::value).unwrap_or(50.0)
+    }
+
+    /// Cutler's RSI: simple average of the last `period` changes, as
+    /// `calculate_rsi` computed it before switching to Wilder smoothing
     #[wasm_bindgen]
-    pub fn calculate_rsi(&self, period: usize) -> f64 {
+    pub fn calculate_rsi_simple(&self, period: usize) -> f64 {
         if self.price_history.len() < period + 1 {
             return 50.0; // Neutral RSI
         }
@@ -312,16 +630,16 @@ impl SiliconMathGuardian {
     /// RSI Failure Swing Detection (Advanced Pattern)
     /// Returns: 1.0 for bullish swing, -1.0 for bearish swing, 0.0 for none
     #[wasm_bindgen]
-    pub fn detect_rsi_failure_swing(&self) -> f64 {
+    pub fn detect_rsi_failure_swing(&mut self) -> f64 {
         if self.price_history.len() < 20 {
             return 0.0;
         }
 
-        let rsi = self.calculate_rsi(14);
-        let prices: Vec<f64> = self.price_history.iter().copied().collect();
-        
+        let rsi = self.calculate_rsi(self.config.rsi_period);
+        let prices = &self.price_history;
+
         // Bullish Failure Swing: RSI below 30, bounces, doesn't hit new low
-        if rsi < 35.0 {
+        if rsi < self.config.failure_swing_oversold {
             let recent_low = prices.iter().rev().take(5).fold(f64::MAX, |a, &b| a.min(b));
             let prior_low = prices.iter().rev().skip(5).take(5).fold(f64::MAX, |a, &b| a.min(b));
             if recent_low > prior_low {
@@ -330,7 +648,7 @@ impl SiliconMathGuardian {
         }
 
         // Bearish Failure Swing: RSI above 70, drops, doesn't hit new high
-        if rsi > 65.0 {
+        if rsi > self.config.failure_swing_overbought {
             let recent_high = prices.iter().rev().take(5).fold(f64::MIN, |a, &b| a.max(b));
             let prior_high = prices.iter().rev().skip(5).take(5).fold(f64::MIN, |a, &b| a.max(b));
             if recent_high < prior_high {
@@ -344,20 +662,11 @@ impl SiliconMathGuardian {
     /// Calculate volatility (standard deviation of returns)
     #[wasm_bindgen]
     pub fn calculate_volatility(&mut self) -> f64 {
-        if self.price_history.len() < 20 {
+        if self.price_history.len() < self.config.min_prices_for_volatility {
             return 0.0;
         }
 
-        let prices: Vec<f64> = self.price_history.iter().copied().collect();
-        let returns: Vec<f64> = prices
-            .windows(2)
-            .map(|w| (w[1] - w[0]) / w[0])
-            .collect();
-
-        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
-        let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / returns.len() as f64;
-        
-        self.last_volatility = variance.sqrt() * 100.0; // Percentage form
+        self.last_volatility = self.return_variance.std_dev() * 100.0; // Percentage form
         self.last_volatility
     }
 
@@ -386,15 +695,18 @@ impl SiliconMathGuardian {
         // =====================================================================
         // SAFETY CHECK 2: Network Latency
         // =====================================================================
-        if network_latency_ms > MAX_LATENCY_MS {
+        let config = self.config.clone();
+        let weights = &config.weights;
+
+        if network_latency_ms > config.max_latency_ms {
             self.audit_log.push(format!(
                 "[HALT] Network latency {}ms exceeds threshold {}ms",
-                network_latency_ms, MAX_LATENCY_MS
+                network_latency_ms, config.max_latency_ms
             ));
             return self.create_signal(
                 Action::HALT,
                 1.0,
-                &format!("Network latency {}ms exceeds {}ms threshold", network_latency_ms, MAX_LATENCY_MS),
+                &format!("Network latency {}ms exceeds {}ms threshold", network_latency_ms, config.max_latency_ms),
                 SignalSource::EmergencyHalt,
                 false,
             );
@@ -404,10 +716,10 @@ impl SiliconMathGuardian {
         // SAFETY CHECK 3: Volatility
         // =====================================================================
         let volatility = self.calculate_volatility();
-        if volatility > VOLATILITY_HALT_THRESHOLD {
+        if volatility > config.volatility_halt_threshold {
             self.audit_log.push(format!(
                 "[HALT] Volatility {:.2}% exceeds threshold {:.2}%",
-                volatility, VOLATILITY_HALT_THRESHOLD
+                volatility, config.volatility_halt_threshold
             ));
             return self.create_signal(
                 Action::HALT,
@@ -421,9 +733,10 @@ impl SiliconMathGuardian {
         // =====================================================================
         // CALCULATE INDICATORS
         // =====================================================================
-        let rsi = self.calculate_rsi(14);
+        let rsi = self.calculate_rsi(config.rsi_period);
         let ofi = self.calculate_ofi();
         let failure_swing = self.detect_rsi_failure_swing();
+        let bar_indicators = self.bar_indicators.values();
 
         // =====================================================================
         // DETERMINISTIC SCORING LOGIC (Golden Dataset Optimized)
@@ -432,65 +745,101 @@ impl SiliconMathGuardian {
         let mut reasons: Vec<String> = Vec::new();
 
         // RSI Logic
-        if rsi < RSI_COLLAPSED {
-            score += 4.0;
+        if rsi < config.rsi_collapsed {
+            score += weights.rsi_extreme;
             reasons.push(format!("RSI Collapsed ({:.1})", rsi));
-        } else if rsi < 30.0 {
-            score += 3.0;
+        } else if rsi < config.rsi_oversold {
+            score += weights.rsi_strong;
             reasons.push(format!("RSI Deep Oversold ({:.1})", rsi));
-        } else if rsi < RSI_BUY_THRESHOLD {
-            score += 1.0;
+        } else if rsi < config.rsi_buy_threshold {
+            score += weights.rsi_mild;
             reasons.push(format!("RSI Below Optimal ({:.1})", rsi));
-        } else if rsi > RSI_SKY_HIGH {
-            score -= 4.0;
+        } else if rsi > config.rsi_sky_high {
+            score -= weights.rsi_extreme;
             reasons.push(format!("RSI Sky High ({:.1})", rsi));
-        } else if rsi > 70.0 {
-            score -= 3.0;
+        } else if rsi > config.rsi_overbought {
+            score -= weights.rsi_strong;
             reasons.push(format!("RSI Overbought ({:.1})", rsi));
-        } else if rsi > RSI_SELL_THRESHOLD {
-            score -= 1.0;
+        } else if rsi > config.rsi_sell_threshold {
+            score -= weights.rsi_mild;
             reasons.push(format!("RSI Above Optimal ({:.1})", rsi));
         }
 
         // OFI Logic
-        if ofi.imbalance > OFI_SIGNIFICANT_THRESHOLD {
-            score += 2.0;
+        if ofi.imbalance > config.ofi_significant_threshold {
+            score += weights.ofi_wall;
             reasons.push(format!("Strong Buy Wall (OFI: {:.2})", ofi.imbalance));
-        } else if ofi.imbalance < -OFI_SIGNIFICANT_THRESHOLD {
-            score -= 2.0;
+        } else if ofi.imbalance < -config.ofi_significant_threshold {
+            score -= weights.ofi_wall;
             reasons.push(format!("Strong Sell Wall (OFI: {:.2})", ofi.imbalance));
         }
 
         // RSI Failure Swing (Advanced Pattern)
         if failure_swing > 0.5 {
-            score += 1.5;
+            score += weights.failure_swing;
             reasons.push("Bullish Failure Swing detected".to_string());
         } else if failure_swing < -0.5 {
-            score -= 1.5;
+            score -= weights.failure_swing;
             reasons.push("Bearish Failure Swing detected".to_string());
         }
 
+        // Bar indicators: only scored once warmed up, so a guardian fed
+        // ticks alone scores exactly as before
+        if let Some(bands) = bar_indicators.bands {
+            if bands.percent_b < 0.0 {
+                score += weights.bollinger;
+                reasons.push(format!("Below Lower Bollinger Band (%B: {:.2})", bands.percent_b));
+            } else if bands.percent_b > 1.0 {
+                score -= weights.bollinger;
+                reasons.push(format!("Above Upper Bollinger Band (%B: {:.2})", bands.percent_b));
+            }
+        }
+
+        if let Some(stochastic) = bar_indicators.stochastic {
+            match stochastic.d {
+                Some(d) if stochastic.k < config.stochastic_oversold && stochastic.k > d => {
+                    score += weights.stochastic;
+                    reasons.push(format!("Stochastic Oversold Turn (%K: {:.1})", stochastic.k));
+                }
+                Some(d) if stochastic.k > config.stochastic_overbought && stochastic.k < d => {
+                    score -= weights.stochastic;
+                    reasons.push(format!("Stochastic Overbought Turn (%K: {:.1})", stochastic.k));
+                }
+                _ => {}
+            }
+        }
+
         // Trend strength confirmation
-        if ofi.trend_strength > 0.5 && score > 0.0 {
-            score += 1.0;
+        if ofi.trend_strength > config.trend_strength_threshold && score > 0.0 {
+            score += weights.trend_confirmation;
             reasons.push("Trend momentum confirms".to_string());
-        } else if ofi.trend_strength < -0.5 && score < 0.0 {
-            score -= 1.0;
+        } else if ofi.trend_strength < -config.trend_strength_threshold && score < 0.0 {
+            score -= weights.trend_confirmation;
             reasons.push("Trend momentum confirms".to_string());
         }
 
+        if let Some(macd) = bar_indicators.macd {
+            if macd.histogram > 0.0 && score > 0.0 {
+                score += weights.macd_confirmation;
+                reasons.push("MACD histogram confirms".to_string());
+            } else if macd.histogram < 0.0 && score < 0.0 {
+                score -= weights.macd_confirmation;
+                reasons.push("MACD histogram confirms".to_string());
+            }
+        }
+
         // =====================================================================
         // DETERMINE ACTION
         // =====================================================================
-        let math_action = if score >= 4.0 {
+        let math_action = if score >= config.action_score_threshold {
             Action::BUY
-        } else if score <= -4.0 {
+        } else if score <= -config.action_score_threshold {
             Action::SELL
         } else {
             Action::HOLD
         };
 
-        let math_confidence = (score.abs() / 8.0).min(0.99);
+        let math_confidence = (score.abs() / config.confidence_score_scale).min(0.99);
 
         // =====================================================================
         // SYMBOLIC CONSENSUS: Neural vs Math arbitration
@@ -501,7 +850,7 @@ impl SiliconMathGuardian {
                 if neural == math_action && math_action != Action::HOLD {
                     (
                         math_action,
-                        (math_confidence + neural_confidence) / 2.0 + 0.1,
+                        (math_confidence + neural_confidence) / 2.0 + config.consensus_bonus,
                         SignalSource::SymbolicConsensus,
                         true,
                     )
@@ -526,7 +875,7 @@ impl SiliconMathGuardian {
                     )
                 }
                 // Case D: Low consensus confidence
-                else if neural_confidence < MIN_CONFIDENCE_THRESHOLD && math_confidence < MIN_CONFIDENCE_THRESHOLD {
+                else if neural_confidence < config.min_confidence_threshold && math_confidence < config.min_confidence_threshold {
                     reasons.push("Insufficient consensus confidence".to_string());
                     (
                         Action::HOLD,
@@ -536,7 +885,7 @@ impl SiliconMathGuardian {
                     )
                 }
                 // Case E: Math neutral, trust high-confidence neural
-                else if math_action == Action::HOLD && neural_confidence > 0.85 {
+                else if math_action == Action::HOLD && neural_confidence > config.neural_override_confidence {
                     (
                         neural,
                         neural_confidence,
@@ -560,7 +909,7 @@ impl SiliconMathGuardian {
                     math_action,
                     math_confidence,
                     SignalSource::MathGuardian,
-                    math_action != Action::HOLD && math_confidence > 0.7,
+                    math_action != Action::HOLD && math_confidence > config.standalone_execute_confidence,
                 )
             }
         };
@@ -584,12 +933,13 @@ impl SiliconMathGuardian {
     // PRIVATE HELPERS
     // =========================================================================
 
-    fn calculate_momentum(&self, prices: &[f64]) -> f64 {
-        if prices.len() < 10 {
+    /// Mean of the last 5 prices against the 5 before them
+    fn calculate_momentum(&self) -> f64 {
+        if !self.momentum_price_sum.is_full() {
             return 0.0;
         }
-        let recent: f64 = prices.iter().rev().take(5).sum::<f64>() / 5.0;
-        let prior: f64 = prices.iter().rev().skip(5).take(5).sum::<f64>() / 5.0;
+        let recent = self.recent_price_sum.sum() / 5.0;
+        let prior = (self.momentum_price_sum.sum() - self.recent_price_sum.sum()) / 5.0;
         (recent - prior) / prior
     }
 
@@ -601,31 +951,10 @@ impl SiliconMathGuardian {
         source: SignalSource,
         can_execute: bool,
     ) -> TradingSignal {
-        let timestamp = js_sys::Date::now() as u64;
+        let timestamp = now_ms();
         
-        // Generate cryptographic proof hash
-        let proof_payload = format!(
-            "TITAN:{}:{}:{}:{}:{}",
-            timestamp,
-            match action {
-                Action::BUY => "BUY",
-                Action::SELL => "SELL",
-                Action::HOLD => "HOLD",
-                Action::HALT => "HALT",
-            },
-            confidence,
-            reasoning,
-            match source {
-                SignalSource::MathGuardian => "MATH",
-                SignalSource::NeuralCortex => "NEURAL",
-                SignalSource::SymbolicConsensus => "CONSENSUS",
-                SignalSource::EmergencyHalt => "HALT",
-            }
-        );
-
-        let mut hasher = Sha256::new();
-        hasher.update(proof_payload.as_bytes());
-        let proof_hash = format!("0x{}", hex::encode(hasher.finalize()));
+        // Generate cryptographic proof hash, bound to the config in force
+        let proof_hash = proof_hash(timestamp, action, confidence, reasoning, source, &self.config_json);
 
         // Immutable audit logging
         self.audit_log.push(format!(
@@ -651,10 +980,113 @@ impl SiliconMathGuardian {
             proof_hash,
             timestamp,
             can_execute,
+            config: self.config_json.clone(),
         }
     }
 }
 
+impl SiliconMathGuardian {
+    /// Rust-side `with_config`: validates, then builds
+    pub fn from_config(config: GuardianConfig) -> Result<Self, ConfigError> {
+        config.validate()?;
+        Ok(Self::build(config))
+    }
+
+    fn build(config: GuardianConfig) -> Self {
+        #[cfg(feature = "console_error_panic_hook")]
+        console_error_panic_hook::set_once();
+
+        let size = config.buffer_size;
+        Self {
+            tick_buffer: VecDeque::with_capacity(size),
+            price_history: VecDeque::with_capacity(size),
+            ofi_history: VecDeque::with_capacity(config.ofi_history_size),
+            kill_switch_active: false,
+            audit_log: Vec::new(),
+            last_volatility: 0.0,
+            rsi_trackers: vec![WilderRsi::new(config.rsi_period)],
+            bar_history: VecDeque::with_capacity(size),
+            dmi_trackers: vec![WilderDmi::new(config.adx_period)],
+            bar_indicators: BarIndicators::new(&config.indicators),
+            bar_series: Vec::new(),
+            bid_volume_sum: RollingSum::new(size),
+            ask_volume_sum: RollingSum::new(size),
+            recent_price_sum: RollingSum::new(5),
+            momentum_price_sum: RollingSum::new(10),
+            return_variance: RollingVariance::new(size - 1),
+            config_json: serde_json::to_string(&config).unwrap_or_default(),
+            config,
+        }
+    }
+}
+
+fn dmi_reading(dmi: Option<Dmi>) -> DmiReading {
+    match dmi {
+        Some(dmi) => DmiReading {
+            plus_di: dmi.plus_di,
+            minus_di: dmi.minus_di,
+            adx: dmi.adx.unwrap_or(0.0),
+            ready: dmi.adx.is_some(),
+        },
+        None => DmiReading {
+            plus_di: 0.0,
+            minus_di: 0.0,
+            adx: 0.0,
+            ready: false,
+        },
+    }
+}
+
+/// SHA-256 over the decision and a digest of the GuardianConfig JSON it was
+/// scored under, so a signal verifies only against its own thresholds
+fn proof_hash(
+    timestamp: u64,
+    action: Action,
+    confidence: f64,
+    reasoning: &str,
+    source: SignalSource,
+    config_json: &str,
+) -> String {
+    let proof_payload = format!(
+        "TITAN:{}:{}:{}:{}:{}:{}",
+        timestamp,
+        match action {
+            Action::BUY => "BUY",
+            Action::SELL => "SELL",
+            Action::HOLD => "HOLD",
+            Action::HALT => "HALT",
+        },
+        confidence,
+        reasoning,
+        match source {
+            SignalSource::MathGuardian => "MATH",
+            SignalSource::NeuralCortex => "NEURAL",
+            SignalSource::SymbolicConsensus => "CONSENSUS",
+            SignalSource::EmergencyHalt => "HALT",
+        },
+        hex::encode(Sha256::digest(config_json.as_bytes()))
+    );
+
+    let mut hasher = Sha256::new();
+    hasher.update(proof_payload.as_bytes());
+    format!("0x{}", hex::encode(hasher.finalize()))
+}
+
+/// Wall-clock milliseconds: `Date.now()` in WASM, the system clock natively
+fn now_ms() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now() as u64
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0)
+    }
+}
+
 // ============================================================================
 // TESTS
 // ============================================================================
@@ -676,6 +1108,225 @@ mod tests {
         assert!(rsi > 70.0, "RSI should be overbought after consistent gains");
     }
 
+    #[test]
+    fn test_rsi_tracked_per_tick_matches_replay() {
+        let mut guardian = SiliconMathGuardian::new();
+        let prices = [100.0, 101.5, 100.8, 102.2, 101.1, 103.0, 102.4, 101.9, 103.6, 104.1];
+
+        for price in prices.iter().take(5) {
+            guardian.ingest_tick(*price, 1.0, 1.0);
+        }
+        // Period 3 is registered mid-stream and seeded from the buffer
+        let early = guardian.calculate_rsi(3);
+        for price in prices.iter().skip(5) {
+            guardian.ingest_tick(*price, 1.0, 1.0);
+        }
+
+        let mut reference = WilderRsi::new(3);
+        let mut replayed = 0.0;
+        for (i, price) in prices.iter().enumerate() {
+            replayed = reference.update(*price).unwrap_or(50.0);
+            if i == 4 {
+                assert_eq!(early, replayed);
+            }
+        }
+        assert_eq!(guardian.calculate_rsi(3), replayed);
+        // The simple average differs once Wilder smoothing has history to weigh
+        assert_ne!(guardian.calculate_rsi_simple(3), replayed);
+        assert_eq!(guardian.calculate_rsi(14), 50.0);
+    }
+
+    #[test]
+    fn test_tracked_rsi_periods_are_capped() {
+        let mut guardian = SiliconMathGuardian::new();
+        for i in 0..60 {
+            guardian.ingest_tick(100.0 + (i as f64 / 3.0).sin(), 1.0, 1.0);
+        }
+
+        for period in 2..40 {
+            guardian.calculate_rsi(period);
+        }
+        assert_eq!(guardian.rsi_trackers.len(), indicators::MAX_TRACKED_PERIODS);
+
+        // Untracked periods still answer, from the buffer
+        let mut reference = WilderRsi::new(30);
+        let expected = guardian.price_history.iter().filter_map(|p| reference.update(*p)).last();
+        assert_eq!(guardian.calculate_rsi(30), expected.unwrap());
+    }
+
+    #[test]
+    fn test_adx_from_bars() {
+        let mut guardian = SiliconMathGuardian::new();
+        assert!(!guardian.calculate_adx(14).ready());
+
+        for i in 0..40 {
+            let base = 100.0 + i as f64;
+            guardian.ingest_bar(base + 1.0, base - 1.0, base);
+        }
+        // Inverted bars are dropped rather than corrupting the trackers
+        guardian.ingest_bar(90.0, 110.0, 100.0);
+
+        let adx = guardian.calculate_adx(14);
+        assert!(adx.ready());
+        assert!(adx.plus_di() > adx.minus_di());
+        assert!(adx.adx() > 25.0, "steady uptrend should read as trending");
+
+        // A period requested late matches one tracked from the start
+        let late = guardian.calculate_adx(5);
+        let mut reference = WilderDmi::new(5);
+        for i in 0..40 {
+            let base = 100.0 + i as f64;
+            reference.update(base + 1.0, base - 1.0, base);
+        }
+        assert_eq!(Some(late.adx()), reference.value().unwrap().adx);
+    }
+
+    #[test]
+    fn test_indicators_on_a_chosen_bar_series() {
+        let mut guardian = SiliconMathGuardian::new();
+        let minute = guardian.add_bar_series(BarType::Time, 60_000.0).unwrap();
+        let ticks = guardian.add_bar_series(BarType::Tick, 10.0).unwrap();
+        let volume = guardian.add_bar_series(BarType::Volume, 120.0).unwrap();
+        assert_eq!(guardian.add_bar_series(BarType::Volume, 0.0), None);
+
+        // One tick per second, trending up within each minute; every other
+        // tick is a book-only update, the rest trade 2 units
+        for i in 0..1_800u64 {
+            let price = 100.0 + (i / 60) as f64 + (i % 60) as f64 * 0.01;
+            let timestamp = 1_000_000 + i * 1_000;
+            if i % 2 == 0 {
+                guardian.ingest_trade_at(price, 1.0, 1.0, 2.0, timestamp);
+            } else {
+                guardian.ingest_tick_at(price, 1.0, 1.0, timestamp);
+            }
+        }
+
+        // First bucket is partial (starts 40s in), the last is still forming
+        assert_eq!(guardian.bar_count(minute), 30);
+        assert_eq!(guardian.bar_count(ticks), 100);
+        // Volume bars count traded size only, not book depth
+        assert_eq!(guardian.bar_count(volume), 15);
+        assert!(guardian.calculate_bar_rsi(minute, 14) > 70.0);
+        assert!(guardian.calculate_bar_adx(minute, 5).ready());
+        assert_eq!(guardian.calculate_bar_rsi(7, 14), 50.0);
+
+        let bars: Vec<serde_json::Value> = serde_json::from_str(&guardian.get_bars(minute)).unwrap();
+        assert_eq!(bars[0]["ticks"], 20);
+        assert_eq!(bars[1]["start_ms"], 1_020_000);
+    }
+
+    // The from-scratch calculations the running state replaced
+    fn batch_ofi(guardian: &SiliconMathGuardian) -> (f64, f64, f64) {
+        let bid: f64 = guardian.tick_buffer.iter().map(|t| t.bid_volume).sum();
+        let ask: f64 = guardian.tick_buffer.iter().map(|t| t.ask_volume).sum();
+        let prices: Vec<f64> = guardian.price_history.iter().copied().collect();
+        let recent = prices.iter().rev().take(5).sum::<f64>() / 5.0;
+        let prior = prices.iter().rev().skip(5).take(5).sum::<f64>() / 5.0;
+        ((bid - ask) / (bid + ask), bid - ask, (recent - prior) / prior)
+    }
+
+    fn batch_volatility(guardian: &SiliconMathGuardian) -> f64 {
+        let prices: Vec<f64> = guardian.price_history.iter().copied().collect();
+        let returns: Vec<f64> = prices.windows(2).map(|w| (w[1] - w[0]) / w[0]).collect();
+        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+        let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / returns.len() as f64;
+        variance.sqrt() * 100.0
+    }
+
+    #[test]
+    fn test_streaming_state_matches_batch() {
+        let mut guardian = SiliconMathGuardian::new();
+
+        for i in 0..350 {
+            let t = i as f64;
+            let price = 100.0 + 5.0 * (t / 11.0).sin() + 0.02 * t;
+            guardian.ingest_tick(price, 1.0 + (t / 3.0).cos().abs(), 1.0 + (t / 5.0).sin().abs());
+
+            if guardian.price_history.len() >= 20 {
+                let (imbalance, delta, momentum) = batch_ofi(&guardian);
+                let ofi = guardian.calculate_ofi();
+                assert!((ofi.imbalance() - imbalance).abs() < 1e-9);
+                assert!((ofi.cumulative_delta() - delta).abs() < 1e-9);
+                assert!((ofi.trend_strength() - momentum).abs() < 1e-9);
+                assert!((guardian.calculate_volatility() - batch_volatility(&guardian)).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bar_indicators_feed_scoring() {
+        let mut guardian = SiliconMathGuardian::new();
+        assert_eq!(guardian.get_indicators().macd(), None);
+
+        // A steady climb, then a sharp break lower on the last bars
+        for i in 0..60 {
+            let close = if i < 55 { 100.0 + i as f64 * 0.5 } else { 127.0 - (i - 54) as f64 * 4.0 };
+            guardian.ingest_bar(close + 0.5, close - 0.5, close);
+        }
+        for _ in 0..30 {
+            guardian.ingest_tick(100.0, 1.0, 1.0);
+        }
+
+        let reading = guardian.get_indicators();
+        assert!(reading.percent_b().unwrap() < 0.0);
+        assert!(reading.macd_histogram().unwrap() < 0.0);
+        assert!(reading.atr().unwrap() > 1.0);
+        assert!(reading.stochastic_k().unwrap() < 20.0);
+
+        let signal = guardian.generate_signal(50, None, 0.0);
+        assert!(signal.reasoning().contains("Below Lower Bollinger Band"));
+    }
+
+    #[test]
+    fn test_config_drives_decisions_and_is_echoed() {
+        let config = GuardianConfig {
+            max_latency_ms: 50,
+            ..GuardianConfig::default()
+        };
+        let mut guardian = SiliconMathGuardian::from_config(config.clone()).unwrap();
+
+        // 100ms is fine under the default 200ms budget but not under 50ms
+        let signal = guardian.generate_signal(100, None, 0.0);
+        assert_eq!(signal.action, Action::HALT);
+        let echoed: GuardianConfig = serde_json::from_str(&signal.config()).unwrap();
+        assert_eq!(echoed, config);
+        assert_eq!(guardian.get_config(), signal.config());
+
+        // The proof re-derives from the echoed config and no other
+        let proof = |config: &str| {
+            proof_hash(signal.timestamp, signal.action, signal.confidence, &signal.reasoning, signal.source, config)
+        };
+        assert_eq!(signal.proof_hash(), proof(&signal.config()));
+        let mut default = SiliconMathGuardian::new();
+        assert_ne!(signal.proof_hash(), proof(&default.get_config()));
+
+        assert_eq!(default.generate_signal(100, None, 0.0).action, Action::HOLD);
+
+        let invalid = GuardianConfig {
+            rsi_buy_threshold: 80.0,
+            ..GuardianConfig::default()
+        };
+        assert!(matches!(SiliconMathGuardian::from_config(invalid), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_bad_tick_cannot_disable_volatility_halt() {
+        let mut guardian = SiliconMathGuardian::new();
+        for _ in 0..50 {
+            guardian.ingest_tick(100.0, 1.0, 1.0);
+        }
+        guardian.ingest_tick(0.0, 1.0, 1.0);
+        guardian.ingest_tick(f64::NAN, 1.0, 1.0);
+        guardian.ingest_tick(100.0, -1.0, 1.0);
+        assert_eq!(guardian.price_history.len(), 50);
+
+        for i in 0..500 {
+            guardian.ingest_tick(if i % 2 == 0 { 100.0 } else { 120.0 }, 1.0, 1.0);
+        }
+        assert!(guardian.calculate_volatility() > VOLATILITY_HALT_THRESHOLD);
+        assert_eq!(guardian.generate_signal(50, None, 0.0).action, Action::HALT);
+    }
+
     #[test]
     fn test_kill_switch() {
         let mut guardian = SiliconMathGuardian::new();